@@ -24,17 +24,33 @@ fn open_url(s: &mut cursive::Cursive, url: url::Url) {
     });
 
     // In a real application, we would have to implement proper error handling for the response.
+    use std::io::Read as _;
+
     let response = ureq::get(url.as_str()).call();
-    match response.content_type() {
-        "text/html" => {
-            let html = response.into_string().expect("Failed to download URL");
-            open_view(s, cursive_markup::MarkupView::html(&html), url);
+    let content_type = response.content_type().to_owned();
+    let charset = response
+        .header("Content-Type")
+        .filter(|content_type| content_type.contains("charset="))
+        .map(|_| response.charset().to_owned());
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .expect("Failed to download URL");
+
+    let renderer: Box<dyn cursive_markup::Renderer> = if content_type == "text/html" {
+        match cursive_markup::html::Renderer::from_bytes(&body, charset.as_deref()) {
+            Ok(renderer) => Box::new(renderer),
+            Err(err) => return show_error(s, err.to_string()),
+        }
+    } else {
+        let body = String::from_utf8_lossy(&body);
+        match cursive_markup::renderer_for(&content_type, &body) {
+            Some(renderer) => renderer,
+            None => return show_error(s, format!("Unsupported content type: {}", content_type)),
         }
-        _ => show_error(
-            s,
-            format!("Unsupported content type: {}", response.content_type()),
-        ),
-    }
+    };
+    open_view(s, cursive_markup::MarkupView::boxed(renderer), url);
 }
 
 fn open_view<R: cursive_markup::Renderer + 'static>(
@@ -0,0 +1,338 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! A renderer for text with ANSI escape sequences.
+//!
+//! *Requires the `ansi` feature.*
+//!
+//! This module provides the [`Renderer`][] struct, a renderer for text that has been formatted
+//! with ANSI escape sequences, for example the output of a command or a log file.  The renderer
+//! supports the SGR sequences for text effects and colors and the OSC 8 sequences for terminal
+//! hyperlinks.  All other escape sequences are ignored.  Other control characters are removed
+//! per default, see [`Renderer::set_control_char_display`][].
+//!
+//! [`Renderer`]: struct.Renderer.html
+//! [`Renderer::set_control_char_display`]: struct.Renderer.html#method.set_control_char_display
+
+use std::convert::TryFrom as _;
+
+use cursive_core::theme;
+use unicode_width::UnicodeWidthChar as _;
+
+use crate::{ControlCharDisplay, Element, RenderedDocument};
+
+/// A renderer for text with ANSI escape sequences.
+///
+/// The text is parsed once when the instance is constructed.  Lines that are longer than the
+/// available width are broken at the last character that fits into the line.  The rendered lines
+/// are mapped to the lines of the text, see [`RenderedDocument::source_line`][].
+///
+/// # Example
+///
+/// ```
+/// let renderer = cursive_markup::ansi::Renderer::new("\x1b[1mbold\x1b[0m text");
+/// let doc = cursive_markup::render_to_document(&renderer, 80);
+/// assert_eq!(doc.size(), (9, 1).into());
+/// ```
+///
+/// Invalid parameters of SGR sequences are ignored:
+///
+/// ```
+/// use cursive_core::theme::{ColorType, Effect};
+///
+/// let text = "\x1b[1mbold\x1b[38;2;300;0;0m still bold\x1b[1000m";
+/// let renderer = cursive_markup::ansi::Renderer::new(text);
+/// let s = cursive_markup::render_to_document(&renderer, 80).to_styled_string();
+/// for span in s.spans() {
+///     assert!(span.attr.effects.contains(Effect::Bold));
+///     assert_eq!(span.attr.color.front, ColorType::InheritParent);
+/// }
+/// ```
+///
+/// [`RenderedDocument::source_line`]: ../struct.RenderedDocument.html#method.source_line
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    lines: Vec<Vec<Element>>,
+    control_char_display: ControlCharDisplay,
+}
+
+/// The state of the parser: the current style and link target.
+#[derive(Debug, Default)]
+struct Parser {
+    lines: Vec<Vec<Element>>,
+    line: Vec<Element>,
+    text: String,
+    column: usize,
+    style: theme::Style,
+    link_target: Option<String>,
+}
+
+impl Renderer {
+    /// Creates a new renderer for the given text.
+    pub fn new(text: &str) -> Renderer {
+        let mut parser = Parser::default();
+        parser.parse(text);
+        Renderer {
+            lines: parser.finish(),
+            control_char_display: ControlCharDisplay::Strip,
+        }
+    }
+
+    /// Sets the display of control characters that are not part of an escape sequence.
+    ///
+    /// Line breaks and tabs are always handled by the renderer.  As the control characters are
+    /// replaced before wrapping, the lines and links are positioned according to the width of the
+    /// replacement.  Per default, control characters are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::ControlCharDisplay;
+    ///
+    /// let mut renderer = cursive_markup::ansi::Renderer::new("press \x03 to quit");
+    /// renderer.set_control_char_display(ControlCharDisplay::Caret);
+    /// let doc = cursive_markup::render_to_document(&renderer, 80);
+    /// assert_eq!(doc.to_styled_string().source(), "press ^C to quit");
+    ///
+    /// renderer.set_control_char_display(ControlCharDisplay::Pictures);
+    /// let doc = cursive_markup::render_to_document(&renderer, 80);
+    /// assert_eq!(doc.to_styled_string().source(), "press \u{2403} to quit");
+    /// ```
+    pub fn set_control_char_display(&mut self, display: ControlCharDisplay) {
+        self.control_char_display = display;
+    }
+}
+
+impl super::Renderer for Renderer {
+    fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
+        let mut doc = RenderedDocument::new(constraint);
+        let width = std::cmp::max(1, constraint.x);
+        for (source_line, line) in self.lines.iter().enumerate() {
+            let mut wrapped: Vec<Element> = Vec::new();
+            let mut x = 0;
+            for element in line {
+                let mut text = String::new();
+                let chars = crate::replace_control_chars(&element.text, self.control_char_display);
+                for c in chars.chars() {
+                    let char_width = c.width().unwrap_or_default();
+                    if x + char_width > width && x > 0 {
+                        if !text.is_empty() {
+                            wrapped.push(element.with_text(std::mem::take(&mut text)));
+                        }
+                        doc.set_source_line(doc.lines.len(), source_line);
+                        doc.push_line(std::mem::take(&mut wrapped));
+                        x = 0;
+                    }
+                    text.push(c);
+                    x += char_width;
+                }
+                if !text.is_empty() {
+                    wrapped.push(element.with_text(text));
+                }
+            }
+            doc.set_source_line(doc.lines.len(), source_line);
+            doc.push_line(wrapped);
+        }
+        doc
+    }
+}
+
+impl Parser {
+    fn parse(&mut self, text: &str) {
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        for c in &mut chars {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                if c == 'm' {
+                                    self.apply_sgr(&params);
+                                }
+                                break;
+                            }
+                            params.push(c);
+                        }
+                    }
+                    Some(']') => {
+                        let mut command = String::new();
+                        while let Some(c) = chars.next() {
+                            if c == '\x07' {
+                                break;
+                            } else if c == '\x1b' {
+                                if chars.peek() == Some(&'\\') {
+                                    chars.next();
+                                }
+                                break;
+                            }
+                            command.push(c);
+                        }
+                        self.apply_osc(&command);
+                    }
+                    _ => {}
+                },
+                '\n' => self.end_line(),
+                '\t' => {
+                    let n = 8 - self.column % 8;
+                    for _ in 0..n {
+                        self.push_char(' ');
+                    }
+                }
+                '\r' => {}
+                c => self.push_char(c),
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<Element>> {
+        if !self.text.is_empty() || !self.line.is_empty() {
+            self.end_line();
+        }
+        self.lines
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.text.push(c);
+        self.column += c.width().unwrap_or_default();
+    }
+
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            self.line.push(Element::new(
+                std::mem::take(&mut self.text),
+                self.style,
+                self.link_target.clone(),
+            ));
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.flush();
+        self.lines.push(std::mem::take(&mut self.line));
+        self.column = 0;
+    }
+
+    /// Applies an OSC sequence.  Only terminal hyperlinks (OSC 8) are supported.
+    fn apply_osc(&mut self, command: &str) {
+        if let Some(link) = command.strip_prefix("8;") {
+            self.flush();
+            let target = link
+                .find(';')
+                .map(|idx| &link[idx + 1..])
+                .unwrap_or_default();
+            self.link_target = if target.is_empty() {
+                None
+            } else {
+                Some(target.to_owned())
+            };
+        }
+    }
+
+    /// Applies an SGR sequence with the given parameters.
+    fn apply_sgr(&mut self, params: &str) {
+        self.flush();
+
+        // Empty parameters default to zero.  Malformed parameters are skipped so that they are not
+        // mistaken for a reset.
+        let params: Vec<Option<u32>> = params
+            .split(&[';', ':'][..])
+            .map(|param| {
+                if param.is_empty() {
+                    Some(0)
+                } else {
+                    param.parse().ok()
+                }
+            })
+            .collect();
+        let mut params = params.into_iter();
+        while let Some(param) = params.next() {
+            let param = match param {
+                Some(param) => param,
+                None => continue,
+            };
+            let style = &mut self.style;
+            if let Some((effect, enabled)) = sgr_effect(param) {
+                if enabled {
+                    style.effects.insert(effect);
+                } else {
+                    style.effects.remove(effect);
+                }
+                continue;
+            }
+            match param {
+                0 => *style = theme::Style::none(),
+                30..=37 => style.color.front = dark((param - 30) as u8),
+                38 => {
+                    if let Some(color) = extended_color(&mut params) {
+                        style.color.front = color;
+                    }
+                }
+                39 => style.color.front = theme::ColorType::InheritParent,
+                40..=47 => style.color.back = dark((param - 40) as u8),
+                48 => {
+                    if let Some(color) = extended_color(&mut params) {
+                        style.color.back = color;
+                    }
+                }
+                49 => style.color.back = theme::ColorType::InheritParent,
+                90..=97 => style.color.front = light((param - 90) as u8),
+                100..=107 => style.color.back = light((param - 100) as u8),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Element {
+    /// Returns a copy of this element with the given text that keeps all other properties.
+    fn with_text(&self, text: String) -> Element {
+        Element {
+            text,
+            ..self.clone()
+        }
+    }
+}
+
+/// Returns the effect that is enabled or disabled by the given SGR parameter, if any.
+fn sgr_effect(param: u32) -> Option<(theme::Effect, bool)> {
+    let effect = match param {
+        1 | 22 => theme::Effect::Bold,
+        3 | 23 => theme::Effect::Italic,
+        4 | 24 => theme::Effect::Underline,
+        5 | 6 | 25 => theme::Effect::Blink,
+        7 | 27 => theme::Effect::Reverse,
+        9 | 29 => theme::Effect::Strikethrough,
+        _ => return None,
+    };
+    Some((effect, param < 20))
+}
+
+fn dark(n: u8) -> theme::ColorType {
+    theme::Color::Dark(theme::BaseColor::from(n)).into()
+}
+
+fn light(n: u8) -> theme::ColorType {
+    theme::Color::Light(theme::BaseColor::from(n)).into()
+}
+
+/// Parses the color of an extended color sequence (`38;5;n` or `38;2;r;g;b`).
+///
+/// All parameters of the color are consumed, even if some of them are invalid.
+fn extended_color<I: Iterator<Item = Option<u32>>>(params: &mut I) -> Option<theme::ColorType> {
+    match params.next()?? {
+        5 => Some(theme::Color::from_256colors(color_component(params.next())?).into()),
+        2 => {
+            let r = color_component(params.next());
+            let g = color_component(params.next());
+            let b = color_component(params.next());
+            Some(theme::Color::Rgb(r?, g?, b?).into())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the given color parameter if it is a valid color component.
+fn color_component(param: Option<Option<u32>>) -> Option<u8> {
+    param.flatten().and_then(|param| u8::try_from(param).ok())
+}
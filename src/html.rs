@@ -16,10 +16,21 @@
 //! [`Renderer`]: struct.Renderer.html
 //! [`Converter`]: trait.Converter.html
 
+mod emoji;
+mod markers;
+
+use std::collections;
+
 use cursive_core::theme;
 use html2text::render::text_renderer;
+use unicode_width::{UnicodeWidthChar as _, UnicodeWidthStr as _};
+
+use crate::{ControlCharDisplay, Element, LandmarkKind, RenderError, RenderedDocument};
 
-use crate::{Element, RenderedDocument};
+/// The additional indentation of the continuation lines of wrapped list items.
+const LIST_HANGING_INDENT: usize = 2;
+
+const SOFT_HYPHEN: char = '\u{ad}';
 
 /// A renderer for HTML documents that uses the default rich text decorator and converter.
 pub type RichRenderer = Renderer<text_renderer::RichDecorator, RichConverter>;
@@ -27,8 +38,8 @@ pub type RichRenderer = Renderer<text_renderer::RichDecorator, RichConverter>;
 /// A renderer for HTML documents.
 ///
 /// This renderer uses [`html2text`][] to parse and render an HTML document.  The provided document
-/// is only parsed once: when the instance is constructed.  Then it is rendered every time the
-/// width of the view changes.
+/// is only parsed when the instance is constructed, see [`parse`][].  Then it is rendered every
+/// time the width of the view changes.
 ///
 /// You can custommize the renderer by settting a custom [`TextDecorator`][] and [`Converter`][].
 /// The [`TextDecorator`][] is used by [`html2text`][] to convert the HTML DOM into annotated
@@ -37,15 +48,174 @@ pub type RichRenderer = Renderer<text_renderer::RichDecorator, RichConverter>;
 ///
 /// Per default, the renderer uses the [`RichDecorator`][] and the [`RichConverter`][].
 ///
+/// The width of the rendered document does not exceed the width of the constraint, or the minimum
+/// width if the constraint is smaller (see [`set_minimum_width`][]), unless wrapping preformatted
+/// lines is disabled with [`set_pre_wrap`][].
+///
 /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+/// [`parse`]: fn.parse.html
 /// [`TextDecorator`]: https://docs.rs/html2text/latest/html2text/render/text_renderer/trait.TextDecorator.html
 /// [`RichDecorator`]: https://docs.rs/html2text/latest/html2text/render/text_renderer/trait.RichDecorator.html
 /// [`Converter`]: trait.Converter.html
 /// [`RichConverter`]: trait.RichConverter.html
+/// [`set_minimum_width`]: #method.set_minimum_width
+/// [`set_pre_wrap`]: #method.set_pre_wrap
 pub struct Renderer<D: text_renderer::TextDecorator + Clone, C: Converter<D::Annotation>> {
     render_tree: html2text::RenderTree,
+    texts: Vec<String>,
     decorator: D,
     converter: C,
+    keep_code_together: bool,
+    pre_wrap: bool,
+    term_style: theme::Style,
+    trim_left_margin: bool,
+    trim_preformatted: bool,
+    hr_char: char,
+    skip_empty_links: bool,
+    script_rendering: ScriptRendering,
+    max_links: Option<usize>,
+    math_rendering: MathRendering,
+    math_style: theme::Style,
+    width_adjust: Option<Box<dyn Fn(usize) -> usize>>,
+    quote_marks: Vec<(String, String)>,
+    minimum_width: usize,
+    ignored_annotations: Option<Box<AnnotationFilter<D::Annotation>>>,
+    list_hanging_indent: bool,
+    normalize_spaces: bool,
+    preformat_background: Option<theme::Color>,
+    emoji_mode: EmojiMode,
+    control_char_display: ControlCharDisplay,
+}
+
+/// A function that returns whether an annotation should be ignored.
+type AnnotationFilter<A> = dyn Fn(&A) -> bool;
+
+/// The rendering of mathematical formulas (`<math>`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MathRendering {
+    /// Render the `alttext` attribute of the formula.
+    ///
+    /// If the formula does not have an `alttext` attribute, its text content is rendered instead.
+    AltText,
+    /// Render the text content of the formula.
+    TextContent,
+    /// Don't render the formula.
+    Hidden,
+}
+
+/// The rendering of subscript (`<sub>`) and superscript (`<sup>`) text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScriptRendering {
+    /// Use the Unicode subscript and superscript characters.
+    ///
+    /// If the text contains characters that don't have a Unicode subscript or superscript
+    /// variant, the caret notation is used instead.
+    Unicode,
+    /// Prefix the text with `_` (subscript) or `^` (superscript).
+    Caret,
+    /// Render the text like normal text.
+    Plain,
+}
+
+/// The rendering of emoji.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmojiMode {
+    /// Render emoji as Unicode characters.
+    Unicode,
+    /// Replace emoji with text shortcodes, for example `:rocket:`.
+    Shortcode,
+    /// Remove emoji.
+    Strip,
+}
+
+/// The features of the HTML renderer that depend on the version of [`html2text`][].
+///
+/// See [`renderer_capabilities`][].
+///
+/// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+/// [`renderer_capabilities`]: fn.renderer_capabilities.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Whether tables are rendered as tables.
+    pub tables: bool,
+    /// Whether text colors from CSS styles are rendered.
+    pub colors: bool,
+    /// Whether struck out text (`<s>`, `<del>`) is annotated.
+    pub strikeout: bool,
+    /// Whether the alternative text of images is rendered.
+    pub image_alt_text: bool,
+    /// Whether the `id` attributes of elements are available as anchors, see
+    /// [`RenderedDocument::anchor`][].
+    ///
+    /// [`RenderedDocument::anchor`]: ../struct.RenderedDocument.html#method.anchor
+    pub anchors: bool,
+}
+
+/// The kind of a [`RichAnnotation`][] without its data.
+///
+/// See [`Renderer::set_ignored_annotations`][].
+///
+/// [`RichAnnotation`]: https://docs.rs/html2text/latest/html2text/render/text_renderer/enum.RichAnnotation.html
+/// [`Renderer::set_ignored_annotations`]: struct.Renderer.html#method.set_ignored_annotations
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RichAnnotationKind {
+    /// Normal text.
+    Default,
+    /// A link.
+    Link,
+    /// An image.
+    Image,
+    /// Emphasized text.
+    Emphasis,
+    /// Strong text.
+    Strong,
+    /// Struck out text.
+    Strikeout,
+    /// Code.
+    Code,
+    /// Preformatted text.
+    Preformat,
+}
+
+impl From<&text_renderer::RichAnnotation> for RichAnnotationKind {
+    fn from(annotation: &text_renderer::RichAnnotation) -> RichAnnotationKind {
+        use text_renderer::RichAnnotation;
+        match annotation {
+            RichAnnotation::Default => RichAnnotationKind::Default,
+            RichAnnotation::Link(_) => RichAnnotationKind::Link,
+            RichAnnotation::Image => RichAnnotationKind::Image,
+            RichAnnotation::Emphasis => RichAnnotationKind::Emphasis,
+            RichAnnotation::Strong => RichAnnotationKind::Strong,
+            RichAnnotation::Strikeout => RichAnnotationKind::Strikeout,
+            RichAnnotation::Code => RichAnnotationKind::Code,
+            RichAnnotation::Preformat(_) => RichAnnotationKind::Preformat,
+        }
+    }
+}
+
+/// Determines the encoding of an HTML document from its byte order mark or a `<meta>` tag.
+///
+/// Only the first 1024 bytes are searched for a `charset` declaration, as recommended by the
+/// HTML specification.
+fn sniff_encoding(bytes: &[u8]) -> &'static encoding_rs::Encoding {
+    if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    let head = String::from_utf8_lossy(&bytes[..std::cmp::min(bytes.len(), 1024)]).to_lowercase();
+    head.match_indices("<meta")
+        .filter_map(|(idx, _)| {
+            let tag = &head[idx..];
+            let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+            let label = &tag[tag.find("charset=")? + "charset=".len()..];
+            let label = label.trim_start_matches(&['"', '\''][..]);
+            let end = label
+                .find(|c: char| c == '"' || c == '\'' || c == ';' || c == '/' || c.is_whitespace())
+                .unwrap_or(label.len());
+            encoding_rs::Encoding::for_label(&label.as_bytes()[..end])
+        })
+        .next()
+        .unwrap_or(encoding_rs::UTF_8)
 }
 
 /// A converter for HTML annotations.
@@ -60,32 +230,862 @@ pub trait Converter<A> {
 
     /// Returns the link target for the given annotation (if any).
     fn get_link<'a>(&self, annotation: &'a A) -> Option<&'a str>;
+
+    /// Merges the styles of all annotations of a string into one style.
+    ///
+    /// The styles are passed in the order of the annotations, starting with the outermost
+    /// annotation.  The default implementation uses [`Style::merge`][], so the styles of inner
+    /// annotations take precedence over the styles of outer annotations.  The styles that are
+    /// set on the renderer, for example with [`Renderer::set_term_style`][], are not passed to
+    /// this method but combined with its result, so they take precedence over the merged style.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::theme::{Effect, Style};
+    /// use cursive_markup::Renderer as _;
+    /// use cursive_markup::html::{Converter, Renderer, RichConverter};
+    /// use html2text::render::text_renderer::{RichAnnotation, RichDecorator};
+    ///
+    /// /// A converter that only uses the style of the outermost annotation.
+    /// struct OuterConverter;
+    ///
+    /// impl Converter<RichAnnotation> for OuterConverter {
+    ///     fn get_style(&self, annotation: &RichAnnotation) -> Option<Style> {
+    ///         RichConverter.get_style(annotation)
+    ///     }
+    ///
+    ///     fn get_link<'a>(&self, annotation: &'a RichAnnotation) -> Option<&'a str> {
+    ///         RichConverter.get_link(annotation)
+    ///     }
+    ///
+    ///     fn merge_styles(&self, styles: &[Style]) -> Style {
+    ///         styles.first().copied().unwrap_or_default()
+    ///     }
+    /// }
+    ///
+    /// let html = "<p><em><a href='https://rust-lang.org'>Rust</a></em></p>";
+    /// let style = |renderer: &dyn cursive_markup::Renderer| {
+    ///     let s = renderer.render((80, 10).into()).to_styled_string();
+    ///     let effects = s.spans().next().unwrap().attr.effects;
+    ///     effects
+    /// };
+    ///
+    /// let effects = style(&Renderer::new(html));
+    /// assert!(effects.contains(Effect::Italic) && effects.contains(Effect::Underline));
+    ///
+    /// let effects = style(&Renderer::custom(html, RichDecorator::new(), OuterConverter));
+    /// assert!(effects.contains(Effect::Italic) && !effects.contains(Effect::Underline));
+    /// ```
+    ///
+    /// [`Style::merge`]: https://docs.rs/cursive_core/latest/cursive_core/theme/struct.Style.html#method.merge
+    /// [`Renderer::set_term_style`]: struct.Renderer.html#method.set_term_style
+    fn merge_styles(&self, styles: &[theme::Style]) -> theme::Style {
+        theme::Style::merge(styles)
+    }
+
+    /// Returns whether text with the given annotation should not be broken across lines.
+    ///
+    /// This is used by [`Renderer::set_keep_code_together`][] to detect code runs.  The default
+    /// implementation returns `false`.
+    ///
+    /// [`Renderer::set_keep_code_together`]: struct.Renderer.html#method.set_keep_code_together
+    fn is_unbreakable(&self, annotation: &A) -> bool {
+        let _ = annotation;
+        false
+    }
+
+    /// Returns whether the given annotation marks a continuation of a wrapped preformatted line.
+    ///
+    /// This is used by [`Renderer::set_pre_wrap`][] to restore the original preformatted lines.
+    /// The default implementation returns `false`.
+    ///
+    /// [`Renderer::set_pre_wrap`]: struct.Renderer.html#method.set_pre_wrap
+    fn is_preformat_continuation(&self, annotation: &A) -> bool {
+        let _ = annotation;
+        false
+    }
+
+    /// Returns whether the given annotation marks preformatted text.
+    ///
+    /// This is used by [`Renderer::set_trim_preformatted`][] to detect preformatted lines.  The
+    /// default implementation returns `false`.
+    ///
+    /// [`Renderer::set_trim_preformatted`]: struct.Renderer.html#method.set_trim_preformatted
+    fn is_preformatted(&self, annotation: &A) -> bool {
+        let _ = annotation;
+        false
+    }
 }
 
 /// A converter for [`RichAnnotation`][].
 ///
 /// Besides the straightforward mappings of links and text effects, this converter styles links
-/// with the underline effect and code snippets with the secondary palette color.
+/// with the underline effect and code snippets with the secondary palette color.  As the color is
+/// a reference to the palette, it is resolved when the document is drawn and follows changes of
+/// the theme.
+///
+/// # Example
+///
+/// ```
+/// use cursive_core::theme::{ColorType, PaletteColor};
+///
+/// let doc = cursive_markup::html::render_to_document("<code>main</code>", 80);
+/// let s = doc.to_styled_string();
+/// let span = s.spans().next().unwrap();
+/// assert_eq!(span.content, "`main`");
+/// assert_eq!(span.attr.color.front, ColorType::Palette(PaletteColor::Secondary));
+/// ```
 ///
 /// [`RichAnnotation`]: https://docs.rs/html2text/latest/html2text/render/text_renderer/enum.RichAnnotation.html
 pub struct RichConverter;
 
+/// Renders the given HTML document with the default settings and width without a view.
+///
+/// This is a shortcut for calling [`render_to_document`][] with a [`RichRenderer`][].
+///
+/// # Example
+///
+/// ```
+/// let doc = cursive_markup::html::render_to_document("<p>Hello <em>world</em></p>", 80);
+/// assert_eq!(doc.size(), (11, 1).into());
+/// ```
+///
+/// [`render_to_document`]: ../fn.render_to_document.html
+/// [`RichRenderer`]: type.RichRenderer.html
+pub fn render_to_document(html: &str, width: usize) -> RenderedDocument {
+    crate::render_to_document(&Renderer::new(html), width)
+}
+
+/// A parsed HTML document that can be shared between renderers.
+///
+/// Use the [`parse`][] function to parse a document and [`Renderer::from_render_tree`][] to
+/// render it.  Besides the render tree of [`html2text`][], the document contains the attribute
+/// values that are not preserved by [`html2text`][], for example the `aria-label` attribute of
+/// links.  A document can also be created from a render tree that has been created by
+/// [`html2text::parse`][] directly.
+///
+/// [`parse`]: fn.parse.html
+/// [`Renderer::from_render_tree`]: struct.Renderer.html#method.from_render_tree
+/// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+/// [`html2text::parse`]: https://docs.rs/html2text/latest/html2text/fn.parse.html
+#[derive(Clone, Debug)]
+pub struct Document {
+    render_tree: html2text::RenderTree,
+    texts: Vec<String>,
+}
+
+impl From<html2text::RenderTree> for Document {
+    fn from(render_tree: html2text::RenderTree) -> Document {
+        Document {
+            render_tree,
+            texts: Vec::new(),
+        }
+    }
+}
+
+/// Parses the given HTML document so that it can be shared between renderers.
+///
+/// See [`Document`][] and [`Renderer::from_render_tree`][].
+///
+/// The document is parsed twice:  First, it is parsed with [`html5ever`][] to mark the elements
+/// that are not supported by [`html2text`][], for example definition terms, horizontal rules and
+/// collapsible sections.  Then the marked document is serialized and parsed by [`html2text`][].
+/// This roughly doubles the parsing time.  If you don't need these elements, you can use
+/// [`html2text::parse`][] instead to parse the document only once.
+///
+/// The `cursive-markup:` prefix is reserved for the ids that are used internally to mark elements
+/// that are not supported by `html2text`.  Ids with this prefix in the document are ignored.
+///
+/// # Example
+///
+/// ```
+/// let html = "<p id='cursive-markup:hr'>a</p><p><b id='cursive-markup:dt-end'></b>b</p>";
+/// let doc = cursive_markup::html::render_to_document(html, 80);
+/// assert_eq!(doc.to_plain_text(), "a\n\nb\n");
+///
+/// let html = "<dl><dt>a<span id='cursive-markup:dt-end'></span>b</dt></dl>";
+/// let doc = cursive_markup::html::render_to_document(html, 80);
+/// assert_eq!(doc.to_ansi(), "\x1b[1;3mab\x1b[0m\n");
+/// ```
+///
+/// [`Document`]: struct.Document.html
+/// [`Renderer::from_render_tree`]: struct.Renderer.html#method.from_render_tree
+/// [`html5ever`]: https://docs.rs/html5ever/latest/html5ever/
+/// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+/// [`html2text::parse`]: https://docs.rs/html2text/latest/html2text/fn.parse.html
+pub fn parse(html: &str) -> Document {
+    let (html, texts) = markers::insert(html);
+    Document {
+        render_tree: html2text::parse(html.as_bytes()),
+        texts,
+    }
+}
+
+/// Returns the features that are supported by the version of [`html2text`][] used by the
+/// renderer.
+///
+/// # Example
+///
+/// ```
+/// let capabilities = cursive_markup::html::renderer_capabilities();
+/// assert!(capabilities.tables);
+/// ```
+///
+/// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+pub fn renderer_capabilities() -> Capabilities {
+    Capabilities {
+        tables: true,
+        colors: false,
+        strikeout: true,
+        image_alt_text: true,
+        anchors: true,
+    }
+}
+
+/// A converter for [`RichAnnotation`][] that uses RGB colors.
+///
+/// This converter works like the [`RichConverter`][], but links and code snippets are styled
+/// with fixed RGB colors instead of palette colors.  On true-color terminals, the output looks
+/// the same regardless of the terminal palette.  Per default, links are blue and underlined and
+/// code snippets are orange.
+///
+/// # Example
+///
+/// ```
+/// use cursive_core::theme::{Color, ColorType};
+/// use cursive_markup::Renderer as _;
+/// use cursive_markup::html::{Renderer, RgbConverter};
+/// use html2text::render::text_renderer::RichDecorator;
+///
+/// let mut converter = RgbConverter::default();
+/// converter.set_code_color(Color::Rgb(255, 0, 0));
+/// let renderer = Renderer::custom("<code>main</code>", RichDecorator::new(), converter);
+/// let s = renderer.render((80, 10).into()).to_styled_string();
+/// let span = s.spans().next().unwrap();
+/// assert_eq!(span.attr.color.front, ColorType::Color(Color::Rgb(255, 0, 0)));
+/// ```
+///
+/// [`RichAnnotation`]: https://docs.rs/html2text/latest/html2text/render/text_renderer/enum.RichAnnotation.html
+/// [`RichConverter`]: struct.RichConverter.html
+#[derive(Clone, Debug)]
+pub struct RgbConverter {
+    link_color: theme::Color,
+    code_color: theme::Color,
+}
+
+impl RgbConverter {
+    /// Sets the color for links.
+    pub fn set_link_color(&mut self, color: theme::Color) {
+        self.link_color = color;
+    }
+
+    /// Sets the color for code snippets.
+    pub fn set_code_color(&mut self, color: theme::Color) {
+        self.code_color = color;
+    }
+}
+
+impl Default for RgbConverter {
+    fn default() -> RgbConverter {
+        RgbConverter {
+            link_color: theme::Color::Rgb(0x3b, 0x8e, 0xea),
+            code_color: theme::Color::Rgb(0xd7, 0x87, 0x00),
+        }
+    }
+}
+
+impl Converter<text_renderer::RichAnnotation> for RgbConverter {
+    fn get_style(&self, annotation: &text_renderer::RichAnnotation) -> Option<theme::Style> {
+        use text_renderer::RichAnnotation;
+        match annotation {
+            RichAnnotation::Link(_) => Some(
+                theme::Style::from(theme::Effect::Underline)
+                    .combine(theme::ColorStyle::front(self.link_color)),
+            ),
+            RichAnnotation::Code => Some(theme::ColorStyle::front(self.code_color).into()),
+            _ => RichConverter.get_style(annotation),
+        }
+    }
+
+    fn is_unbreakable(&self, annotation: &text_renderer::RichAnnotation) -> bool {
+        RichConverter.is_unbreakable(annotation)
+    }
+
+    fn is_preformat_continuation(&self, annotation: &text_renderer::RichAnnotation) -> bool {
+        RichConverter.is_preformat_continuation(annotation)
+    }
+
+    fn is_preformatted(&self, annotation: &text_renderer::RichAnnotation) -> bool {
+        RichConverter.is_preformatted(annotation)
+    }
+
+    fn get_link<'a>(&self, annotation: &'a text_renderer::RichAnnotation) -> Option<&'a str> {
+        RichConverter.get_link(annotation)
+    }
+}
+
 impl Renderer<text_renderer::RichDecorator, RichConverter> {
     /// Creates a new renderer for the given HTML document using the default settings.
+    ///
+    /// As HTML parsing is error-tolerant, this never fails.
     pub fn new(html: &str) -> Renderer<text_renderer::RichDecorator, RichConverter> {
         Renderer::custom(html, text_renderer::RichDecorator::new(), RichConverter)
     }
+
+    /// Creates a new renderer for the given HTML document using the default settings, returning
+    /// an error if the document cannot be prepared for rendering.
+    ///
+    /// As HTML parsing is error-tolerant, this currently never fails for string input and returns
+    /// the same renderer as [`new`][].
+    ///
+    /// [`new`]: #method.new
+    pub fn try_new(
+        html: &str,
+    ) -> Result<Renderer<text_renderer::RichDecorator, RichConverter>, RenderError> {
+        Ok(Renderer::new(html))
+    }
+
+    /// Creates a new renderer for the given encoded HTML document using the default settings.
+    ///
+    /// If `charset` is set, the document is decoded using this character encoding.  Otherwise, the
+    /// encoding is determined from the byte order mark or from a `<meta>` tag at the start of the
+    /// document.  If the encoding cannot be determined, UTF-8 is used.  Malformed byte sequences
+    /// are replaced with the replacement character `�`.  An error is only returned if the given
+    /// charset is not supported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::Renderer as _;
+    /// use cursive_markup::html::Renderer;
+    ///
+    /// let html = b"<p>\xe4\xf6\xfc</p>";
+    /// let renderer = Renderer::from_bytes(html, Some("latin1")).unwrap();
+    /// assert_eq!(renderer.render((80, 10).into()).size(), (3, 1).into());
+    ///
+    /// let renderer = Renderer::from_bytes(b"<p>a\xffb</p>", Some("utf-8")).unwrap();
+    /// assert_eq!(renderer.render((80, 10).into()).to_plain_text(), "a\u{fffd}b\n");
+    ///
+    /// assert!(Renderer::from_bytes(html, Some("unknown")).is_err());
+    /// ```
+    pub fn from_bytes(
+        bytes: &[u8],
+        charset: Option<&str>,
+    ) -> Result<Renderer<text_renderer::RichDecorator, RichConverter>, RenderError> {
+        let encoding = match charset {
+            Some(charset) => encoding_rs::Encoding::for_label(charset.trim().as_bytes())
+                .ok_or_else(|| RenderError::UnknownCharset(charset.to_owned()))?,
+            None => sniff_encoding(bytes),
+        };
+        let (html, _, _) = encoding.decode(bytes);
+        Renderer::try_new(&html)
+    }
+
+    /// Sets the kinds of annotations that should be ignored.
+    ///
+    /// Ignored annotations don't affect the style of the text and don't create links, for example
+    /// to display the text without emphasis.  Per default, no annotations are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::Renderer as _;
+    /// use cursive_markup::html::{Renderer, RichAnnotationKind};
+    ///
+    /// let mut renderer = Renderer::new("<p><em>Hello</em> world</p>");
+    /// renderer.set_ignored_annotations(vec![RichAnnotationKind::Emphasis].into_iter().collect());
+    /// let s = renderer.render((80, 10).into()).to_styled_string();
+    /// assert!(s.spans().all(|span| span.attr.effects.is_empty()));
+    /// ```
+    pub fn set_ignored_annotations(&mut self, kinds: collections::HashSet<RichAnnotationKind>) {
+        self.ignored_annotations = if kinds.is_empty() {
+            None
+        } else {
+            Some(Box::new(move |annotation| {
+                kinds.contains(&RichAnnotationKind::from(annotation))
+            }))
+        };
+    }
 }
 
 impl<D: text_renderer::TextDecorator + Clone, C: Converter<D::Annotation>> Renderer<D, C> {
     /// Creates a new renderer for the given HTML document using a custom decorator and converter.
     pub fn custom(html: &str, decorator: D, converter: C) -> Renderer<D, C> {
+        Renderer::from_render_tree(parse(html), decorator, converter)
+    }
+
+    /// Creates a new renderer for an already parsed HTML document.
+    ///
+    /// This makes it possible to parse a document once and to share it between multiple
+    /// renderers.  Use the [`parse`][] function to parse the document:  If the render tree has
+    /// been created by [`html2text::parse`][] directly, the renderer cannot detect the elements
+    /// that are not handled by [`html2text`][], for example definition terms and horizontal rules.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    /// use cursive_markup::html::{Renderer, RichConverter};
+    /// use html2text::render::text_renderer::RichDecorator;
+    ///
+    /// let doc = cursive_markup::html::parse(r#"<a href="/" aria-label='a "b" c:d'>x</a>"#);
+    /// let renderer = Renderer::from_render_tree(doc.clone(), RichDecorator::new(), RichConverter);
+    /// let mut view = cursive_markup::MarkupView::with_renderer(renderer);
+    /// view.layout((80, 10).into());
+    /// assert_eq!(view.focused_link_description(), Some(r#"a "b" c:d"#));
+    /// ```
+    ///
+    /// [`parse`]: fn.parse.html
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    /// [`html2text::parse`]: https://docs.rs/html2text/latest/html2text/fn.parse.html
+    pub fn from_render_tree<T: Into<Document>>(
+        render_tree: T,
+        decorator: D,
+        converter: C,
+    ) -> Renderer<D, C> {
+        let Document { render_tree, texts } = render_tree.into();
         Renderer {
-            render_tree: html2text::parse(html.as_bytes()),
+            render_tree,
+            texts,
             decorator,
             converter,
+            keep_code_together: false,
+            pre_wrap: true,
+            term_style: theme::Effect::Bold.into(),
+            trim_left_margin: false,
+            trim_preformatted: true,
+            hr_char: '─',
+            skip_empty_links: true,
+            script_rendering: ScriptRendering::Plain,
+            max_links: None,
+            math_rendering: MathRendering::AltText,
+            math_style: theme::Effect::Italic.into(),
+            width_adjust: None,
+            quote_marks: Vec::new(),
+            minimum_width: 5,
+            ignored_annotations: None,
+            list_hanging_indent: false,
+            normalize_spaces: true,
+            preformat_background: None,
+            emoji_mode: EmojiMode::Unicode,
+            control_char_display: ControlCharDisplay::Strip,
         }
     }
+
+    /// Sets whether code runs should be kept together on one line.
+    ///
+    /// If this option is enabled and a run of unbreakable text (see
+    /// [`Converter::is_unbreakable`][]) has been split across two lines by [`html2text`][], the
+    /// renderer moves the run to the next line if it fits there.  Adjacent `<code>` elements are
+    /// separate runs, so they can still be placed on different lines.  Per default, this option
+    /// is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let html = "<p>xx yy <code>aaa bbb</code> zz</p>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// let doc = cursive_markup::render_to_document(&renderer, 12);
+    /// assert_eq!(doc.to_plain_text(), "xx yy `aaa\nbbb` zz\n");
+    ///
+    /// renderer.set_keep_code_together(true);
+    /// let doc = cursive_markup::render_to_document(&renderer, 12);
+    /// assert_eq!(doc.to_plain_text(), "xx yy\n`aaa bbb` zz\n");
+    ///
+    /// let html = "<p>xx yy <code>aaa</code> <code>bbb</code> zz</p>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// renderer.set_keep_code_together(true);
+    /// let doc = cursive_markup::render_to_document(&renderer, 14);
+    /// assert_eq!(doc.to_plain_text(), "xx yy `aaa`\n`bbb` zz\n");
+    /// ```
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    /// [`Converter::is_unbreakable`]: trait.Converter.html#method.is_unbreakable
+    pub fn set_keep_code_together(&mut self, keep: bool) {
+        self.keep_code_together = keep;
+    }
+
+    /// Sets whether long lines in preformatted blocks should be wrapped.
+    ///
+    /// If this option is disabled, preformatted lines that have been wrapped by [`html2text`][]
+    /// are joined again (see [`Converter::is_preformat_continuation`][]), so the document can be
+    /// wider than the available width.  This corresponds to the CSS `white-space: pre` setting.
+    /// The option applies to all preformatted blocks as the renderer does not evaluate CSS.  Per
+    /// default, preformatted lines are wrapped (`white-space: pre-wrap`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::Renderer as _;
+    ///
+    /// let mut renderer = cursive_markup::html::Renderer::new("<pre>aaaa bbbb cccc</pre>");
+    /// assert_eq!(renderer.render((9, 10).into()).size(), (9, 2).into());
+    ///
+    /// renderer.set_pre_wrap(false);
+    /// assert_eq!(renderer.render((9, 10).into()).size(), (14, 1).into());
+    /// ```
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    /// [`Converter::is_preformat_continuation`]: trait.Converter.html#method.is_preformat_continuation
+    pub fn set_pre_wrap(&mut self, wrap: bool) {
+        self.pre_wrap = wrap;
+    }
+
+    /// Sets the style for the terms in definition lists (`<dt>`).
+    ///
+    /// This style is combined with the style determined by the converter after the styles of the
+    /// annotations have been merged with [`Converter::merge_styles`][], so it takes precedence
+    /// over them.  Per default, terms are printed in bold.  The definitions (`<dd>`) are indented
+    /// by [`html2text`][], and nested definition lists are indented further.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let html = "<dl><dt>a</dt><dd>b<dl><dt>c</dt><dd>d</dd></dl></dd></dl>";
+    /// let doc = cursive_markup::html::render_to_document(html, 80);
+    /// assert_eq!(doc.to_plain_text(), "a\n  b\n\n  c\n    d\n");
+    /// ```
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    /// [`Converter::merge_styles`]: trait.Converter.html#method.merge_styles
+    pub fn set_term_style(&mut self, style: theme::Style) {
+        self.term_style = style;
+    }
+
+    /// Sets whether the common left margin of all lines should be removed.
+    ///
+    /// If this option is enabled, the renderer determines the leading whitespace that all
+    /// non-blank lines have in common and removes it, so that deeply nested content does not waste
+    /// horizontal space.  Per default, this option is disabled.
+    pub fn set_trim_left_margin(&mut self, trim: bool) {
+        self.trim_left_margin = trim;
+    }
+
+    /// Sets whether preformatted lines should be dedented by [`set_trim_left_margin`][].
+    ///
+    /// If this option is disabled, preformatted lines keep their indentation and are ignored when
+    /// determining the common left margin.  Per default, this option is enabled.
+    ///
+    /// [`set_trim_left_margin`]: #method.set_trim_left_margin
+    pub fn set_trim_preformatted(&mut self, trim: bool) {
+        self.trim_preformatted = trim;
+    }
+
+    /// Sets the character that is used to draw horizontal rules (`<hr>`).
+    ///
+    /// Horizontal rules are drawn as a line of this character that spans the available width.
+    /// Per default, the box drawing character `─` is used.
+    pub fn set_hr_char(&mut self, c: char) {
+        self.hr_char = c;
+    }
+
+    /// Sets whether links with an empty target should be skipped.
+    ///
+    /// If this option is enabled, links with an empty target (`<a href="">`) are rendered as
+    /// plain text so that they cannot be focused.  Named anchors without a link target (`<a
+    /// name="…">`) are never rendered as links, but they are added as anchors to the rendered
+    /// document, see [`RenderedDocument::anchor`][].  Per default, this option is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::Renderer as _;
+    ///
+    /// let html = r#"<p><a name="x">text</a> <a href="">text</a></p>"#;
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// let doc = renderer.render((80, 10).into());
+    /// assert_eq!(doc.link_at((0, 0).into()), None);
+    /// assert_eq!(doc.link_at((5, 0).into()), None);
+    /// assert_eq!(doc.anchor("x"), Some(0));
+    ///
+    /// renderer.set_skip_empty_links(false);
+    /// let doc = renderer.render((80, 10).into());
+    /// assert_eq!(doc.link_at((0, 0).into()), None);
+    /// assert_eq!(doc.link_at((5, 0).into()), Some(0));
+    /// ```
+    ///
+    /// [`RenderedDocument::anchor`]: ../struct.RenderedDocument.html#method.anchor
+    pub fn set_skip_empty_links(&mut self, skip: bool) {
+        self.skip_empty_links = skip;
+    }
+
+    /// Sets how subscript (`<sub>`) and superscript (`<sup>`) text should be rendered.
+    ///
+    /// Per default, subscript and superscript text is rendered like normal text
+    /// ([`ScriptRendering::Plain`][]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::html::ScriptRendering;
+    ///
+    /// let mut renderer = cursive_markup::html::Renderer::new("<p>x<sup>2</sup> x<sup>y</sup></p>");
+    /// renderer.set_script_rendering(ScriptRendering::Unicode);
+    /// let doc = cursive_markup::render_to_document(&renderer, 80);
+    /// assert_eq!(doc.size(), (5, 1).into());
+    /// ```
+    ///
+    /// [`ScriptRendering::Plain`]: enum.ScriptRendering.html#variant.Plain
+    pub fn set_script_rendering(&mut self, rendering: ScriptRendering) {
+        self.script_rendering = rendering;
+    }
+
+    /// Sets the maximum number of links in the rendered document.
+    ///
+    /// Links that exceed this limit remain visible with their style, but they cannot be focused
+    /// or selected, see [`RenderedDocument::set_max_links`][].  Per default, the number of links
+    /// is not limited.
+    ///
+    /// [`RenderedDocument::set_max_links`]: ../struct.RenderedDocument.html#method.set_max_links
+    pub fn set_max_links(&mut self, max_links: Option<usize>) {
+        self.max_links = max_links;
+    }
+
+    /// Sets how mathematical formulas (`<math>`) should be rendered.
+    ///
+    /// The renderer does not typeset formulas.  Instead, it renders a textual representation of
+    /// the formula, see [`MathRendering`][].  Per default, the `alttext` attribute is used if
+    /// it is present ([`MathRendering::AltText`][]).  The text of the formula is processed like
+    /// normal text, for example with the [control character display][`set_control_char_display`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::Renderer as _;
+    /// use cursive_markup::ControlCharDisplay;
+    /// use cursive_markup::html::MathRendering;
+    ///
+    /// let html = "<p><math>x&nbsp;y</math> <math alttext='a\x07b'>z</math></p>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// renderer.set_control_char_display(ControlCharDisplay::Caret);
+    /// let doc = renderer.render((80, 10).into());
+    /// assert_eq!(doc.to_plain_text(), "x y a^Gb\n");
+    ///
+    /// renderer.set_math_rendering(MathRendering::TextContent);
+    /// let doc = renderer.render((80, 10).into());
+    /// assert_eq!(doc.to_plain_text(), "x y z\n");
+    /// ```
+    ///
+    /// [`MathRendering`]: enum.MathRendering.html
+    /// [`set_control_char_display`]: #method.set_control_char_display
+    /// [`MathRendering::AltText`]: enum.MathRendering.html#variant.AltText
+    pub fn set_math_rendering(&mut self, rendering: MathRendering) {
+        self.math_rendering = rendering;
+    }
+
+    /// Sets the rendering of emoji.
+    ///
+    /// On terminals without emoji fonts, emoji are often displayed as boxes.  With
+    /// [`EmojiMode::Shortcode`][], emoji are replaced with text shortcodes like `:rocket:`.
+    /// Sequences of multiple code points, for example emoji with skin tone modifiers or flags,
+    /// are replaced with one shortcode.  Emoji without a known shortcode are replaced with their
+    /// code point, for example `:U+1F9A0:`.  With [`EmojiMode::Strip`][], emoji are removed.
+    /// Symbols that are displayed as text per default, for example `🅰`, are only replaced if they
+    /// are followed by the emoji variation selector.  As the text is replaced after wrapping,
+    /// lines that become wider than the render width are wrapped again.  Per default, emoji are
+    /// rendered as Unicode characters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::Renderer as _;
+    /// use cursive_markup::html::EmojiMode;
+    ///
+    /// let mut renderer = cursive_markup::html::Renderer::new("<p>Launch 🚀👍🏽</p>");
+    /// renderer.set_emoji_mode(EmojiMode::Shortcode);
+    /// let doc = renderer.render((80, 10).into());
+    /// assert_eq!(doc.to_styled_string().source(), "Launch :rocket::+1:");
+    ///
+    /// let html = "<p>Type 🅰 or 🅰\u{fe0f} to win 🦀 a prize</p>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// renderer.set_emoji_mode(EmojiMode::Shortcode);
+    /// let doc = renderer.render((16, 10).into());
+    /// assert_eq!(doc.to_plain_text(), "Type 🅰 or\n:U+1F170: to\nwin :crab: a\nprize\n");
+    /// assert!(doc.size().x <= 16);
+    /// ```
+    ///
+    /// [`EmojiMode::Shortcode`]: enum.EmojiMode.html#variant.Shortcode
+    /// [`EmojiMode::Strip`]: enum.EmojiMode.html#variant.Strip
+    pub fn set_emoji_mode(&mut self, mode: EmojiMode) {
+        self.emoji_mode = mode;
+    }
+
+    /// Sets the display of control characters.
+    ///
+    /// [`html2text`][] removes the control characters from the text of the document, but they
+    /// can remain in the text that is inserted by the renderer, for example in the alternative
+    /// text of formulas.  The characters are replaced after [`html2text`][] has wrapped the text,
+    /// so lines that become wider than the render width are wrapped again.  Per default, control
+    /// characters are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::ControlCharDisplay;
+    ///
+    /// let html = "<p>Press <math alttext='Ctrl+&#3;'><mi>c</mi></math> to quit.</p>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// let doc = cursive_markup::render_to_document(&renderer, 20);
+    /// assert_eq!(doc.to_plain_text(), "Press Ctrl+ to quit.\n");
+    ///
+    /// renderer.set_control_char_display(ControlCharDisplay::Caret);
+    /// let doc = cursive_markup::render_to_document(&renderer, 20);
+    /// assert_eq!(doc.to_plain_text(), "Press Ctrl+^C to\nquit.\n");
+    ///
+    /// renderer.set_control_char_display(ControlCharDisplay::Pictures);
+    /// let doc = cursive_markup::render_to_document(&renderer, 20);
+    /// assert_eq!(doc.to_plain_text(), "Press Ctrl+\u{2403} to\nquit.\n");
+    /// ```
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    pub fn set_control_char_display(&mut self, display: ControlCharDisplay) {
+        self.control_char_display = display;
+    }
+
+    /// Sets the background color for preformatted lines, for example code blocks (`<pre>`).
+    ///
+    /// If a background color is set, preformatted lines are padded with spaces to the render
+    /// width and the background color is applied to the whole line so that code blocks have a
+    /// visible boundary.  Per default, no background color is set.
+    pub fn set_preformat_background(&mut self, color: Option<theme::Color>) {
+        self.preformat_background = color;
+    }
+
+    /// Sets the style for mathematical formulas (`<math>`).
+    ///
+    /// This style is combined with the style determined by the converter.  Per default, formulas
+    /// are printed in italics.
+    pub fn set_math_style(&mut self, style: theme::Style) {
+        self.math_style = style;
+    }
+
+    /// Sets the quotation marks for inline quotes (`<q>`).
+    ///
+    /// The text of an inline quote is enclosed in the opening and closing mark of the first pair.
+    /// Nested quotes use the following pairs, starting again with the first pair if there are
+    /// more nesting levels than pairs.  If no pairs are given, no quotation marks are added.  Per
+    /// default, no quotation marks are added.
+    ///
+    /// The opening mark has the style and the link target of the first text of the quote, and the
+    /// closing mark those of the last text of the quote.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::theme::Effect;
+    /// use cursive_core::View as _;
+    ///
+    /// let html = "<p>He said <em><q>Hello <q>World</q></q></em>.</p>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// let doc = cursive_markup::render_to_document(&renderer, 40);
+    /// assert_eq!(doc.to_plain_text(), "He said Hello World.\n");
+    ///
+    /// renderer.set_inline_quote_marks(&[("“", "”"), ("‘", "’")]);
+    /// let doc = cursive_markup::render_to_document(&renderer, 40);
+    /// assert_eq!(doc.to_plain_text(), "He said “Hello ‘World’”.\n");
+    /// let styled = doc.to_styled_string();
+    /// let italic: Vec<_> = styled
+    ///     .spans()
+    ///     .filter(|span| span.attr.effects.contains(Effect::Italic))
+    ///     .map(|span| span.content)
+    ///     .collect();
+    /// assert_eq!(italic.concat(), "“Hello ‘World’”");
+    ///
+    /// // The quotation marks of a quote in a link are part of the link.
+    /// let html = "<a href='target'><q>Link</q></a>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// renderer.set_inline_quote_marks(&[("“", "”")]);
+    /// let mut view = cursive_markup::MarkupView::with_renderer(renderer);
+    /// view.layout((40, 1).into());
+    /// assert_eq!(view.focused_link_text().as_deref(), Some("“Link”"));
+    /// ```
+    pub fn set_inline_quote_marks(&mut self, marks: &[(&str, &str)]) {
+        self.quote_marks = marks
+            .iter()
+            .map(|(open, close)| ((*open).to_owned(), (*close).to_owned()))
+            .collect();
+    }
+
+    /// Sets the minimum width that is passed to [`html2text`][].
+    ///
+    /// If the available width is smaller than the minimum width, the document is rendered with
+    /// the minimum width and is wider than the view.  Per default, the minimum width is five
+    /// columns as [`html2text`][] may not be able to render some elements, for example nested
+    /// lists, if the width is smaller.  Setting a lower minimum width can lead to a panic in
+    /// [`html2text`][] for these documents.  If the width is zero, an empty document is returned.
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    pub fn set_minimum_width(&mut self, width: usize) {
+        self.minimum_width = width;
+    }
+
+    /// Sets whether the continuation lines of wrapped list items should have a hanging indent.
+    ///
+    /// [`html2text`][] aligns the continuation lines of a list item with the text of its first
+    /// line.  If this option is enabled, the paragraphs of list items are wrapped again so that
+    /// their continuation lines are indented by two more columns than the first line.  The
+    /// indent is relative to the text of the item, so it does not add up for nested lists.
+    /// Preformatted text is not changed.  Per default, the continuation lines are not indented.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let html = "<ul><li>one two three four five six</li></ul>";
+    /// let mut renderer = cursive_markup::html::Renderer::new(html);
+    /// let doc = cursive_markup::render_to_document(&renderer, 16);
+    /// assert_eq!(doc.to_plain_text(), "* one two three\n  four five six\n");
+    ///
+    /// renderer.set_list_hanging_indent(true);
+    /// let doc = cursive_markup::render_to_document(&renderer, 16);
+    /// assert_eq!(doc.to_plain_text(), "* one two three\n    four five\n    six\n");
+    /// ```
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    pub fn set_list_hanging_indent(&mut self, indent: bool) {
+        self.list_hanging_indent = indent;
+    }
+
+    /// Sets whether soft hyphens and non-breaking spaces should be normalized.
+    ///
+    /// Lines are never wrapped at non-breaking spaces (`&nbsp;`).  If this option is enabled,
+    /// they are rendered as regular spaces with a width of one cell.  Soft hyphens (`&shy;`) are
+    /// used as wrap points:  If the first word of a line contains soft hyphens, the longest part
+    /// of the word that fits into the previous line is moved there and rendered with a hyphen.
+    /// All other soft hyphens are removed.  If this option is disabled, both characters are kept
+    /// as they are and [`html2text`][] does not wrap lines at soft hyphens.  Per default, they are
+    /// normalized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let html = "<p>Press \u{f8ff}&nbsp;Apple for extra&shy;ordi&shy;nary results</p>";
+    /// let doc = cursive_markup::html::render_to_document(html, 24);
+    /// assert_eq!(
+    ///     doc.to_plain_text(),
+    ///     "Press \u{f8ff} Apple for extra-\nordinary results\n",
+    /// );
+    /// ```
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    pub fn set_normalize_spaces(&mut self, normalize: bool) {
+        self.normalize_spaces = normalize;
+    }
+
+    /// Sets a function that adjusts the width that is passed to [`html2text`][].
+    ///
+    /// The function receives the available width, i. e. the width of the view limited by
+    /// [`MarkupView::set_maximum_width`][], and returns the width that should be used for
+    /// rendering, for example to reserve some columns for a custom decorator.  The renderer
+    /// always uses at least the minimum width, even if the adjusted width is smaller, see
+    /// [`set_minimum_width`][].  Per default, the available width is used.
+    ///
+    /// [`html2text`]: https://docs.rs/html2text/latest/html2text/
+    /// [`MarkupView::set_maximum_width`]: ../struct.MarkupView.html#method.set_maximum_width
+    /// [`set_minimum_width`]: #method.set_minimum_width
+    pub fn set_width_adjust<F: Fn(usize) -> usize + 'static>(&mut self, f: F) {
+        self.width_adjust = Some(Box::new(f));
+    }
 }
 
 impl<D: text_renderer::TextDecorator + Clone, C: Converter<D::Annotation>> super::Renderer
@@ -93,38 +1093,1072 @@ impl<D: text_renderer::TextDecorator + Clone, C: Converter<D::Annotation>> super
 {
     fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
         let mut doc = RenderedDocument::new(constraint);
+        doc.set_max_links(self.max_links);
+        let width = match &self.width_adjust {
+            Some(f) => f(constraint.x),
+            None => constraint.x,
+        };
+        let width = std::cmp::max(self.minimum_width, width);
+        if width == 0 {
+            return doc;
+        }
 
-        let lines = self
+        let tagged_lines = self
             .render_tree
             .clone()
-            .render(std::cmp::max(5, constraint.x), self.decorator.clone())
+            .render(width, self.decorator.clone())
             .into_lines();
-        for line in lines {
-            let mut elements = Vec::new();
-            for element in line.iter() {
-                if let text_renderer::TaggedLineElement::Str(ts) = element {
-                    let styles: Vec<_> = ts
-                        .tag
-                        .iter()
-                        .filter_map(|a| self.converter.get_style(a))
-                        .collect();
-                    let link_target = ts
-                        .tag
-                        .iter()
-                        .find_map(|a| self.converter.get_link(a))
-                        .map(ToOwned::to_owned);
-                    elements.push(Element::new(
-                        ts.s.clone(),
-                        theme::Style::merge(&styles),
-                        link_target,
-                    ));
+        let mut state = RenderState::default();
+        let mut lines: Vec<Line> = Vec::new();
+        for line in &tagged_lines {
+            let line = self.convert_line(line, &mut state);
+            if !self.pre_wrap && line.continuation {
+                if let Some(prev) = lines.last_mut() {
+                    prev.elements.extend(line.elements);
+                    prev.anchors.extend(line.anchors);
+                    prev.headings.extend(line.headings);
+                    prev.summary_ends.extend(line.summary_ends);
+                    prev.section_ends.extend(line.section_ends);
+                    prev.landmark_starts.extend(line.landmark_starts);
+                    prev.landmark_ends.extend(line.landmark_ends);
+                    continue;
                 }
             }
-            doc.push_line(elements);
+            lines.push(line);
+        }
+
+        if self.keep_code_together {
+            keep_runs_together(&mut lines, width);
+        }
+        lines = wrap_widened_lines(lines, width);
+        if self.list_hanging_indent {
+            lines = indent_list_items(lines, width);
+        }
+        // The spaces are normalized after all wrapping has been done.
+        if self.normalize_spaces {
+            hyphenate(&mut lines, width);
+        }
+        for line in &mut lines {
+            self.normalize_line(line);
+        }
+        if self.trim_left_margin {
+            trim_left_margin(&mut lines, self.trim_preformatted);
+        }
+
+        let mut sections = vec![(0, 0); state.sections.len()];
+        let mut landmarks = vec![(0, 0); state.landmarks.len()];
+        for mut line in lines {
+            if line.rule {
+                self.fill_rule(&mut line, width);
+            }
+            line.trim_end();
+            if let Some(background) = self.preformat_background.filter(|_| line.preformatted) {
+                fill_background(&mut line, width, background);
+            }
+            for anchor in line.anchors {
+                doc.push_anchor(anchor);
+            }
+            for heading in line.headings {
+                let (level, text) = &state.headings[heading];
+                let text: Vec<_> = text.split_whitespace().collect();
+                doc.push_heading(*level, text.join(" "));
+            }
+            for landmark in line.landmark_starts {
+                landmarks[landmark] = (doc.size().y, doc.size().y);
+            }
+            doc.push_line(line.elements.into_iter().map(|(element, _)| element));
+            let y = doc.size().y;
+            for landmark in line.landmark_ends {
+                landmarks[landmark].1 = y;
+            }
+            for section in line.summary_ends {
+                sections[section] = (y, y);
+            }
+            for section in line.section_ends {
+                sections[section].1 = y;
+            }
+        }
+        for ((start, end), open) in sections.into_iter().zip(state.sections) {
+            doc.push_section(start..end, open);
+        }
+        for ((start, end), kind) in landmarks.into_iter().zip(state.landmarks) {
+            doc.push_landmark(kind, start..end);
         }
 
         doc
     }
+
+    fn minimum_width(&self) -> usize {
+        self.minimum_width
+    }
+}
+
+impl<D: text_renderer::TextDecorator + Clone, C: Converter<D::Annotation>> Renderer<D, C> {
+    fn is_ignored(&self, annotation: &D::Annotation) -> bool {
+        self.ignored_annotations
+            .as_ref()
+            .map(|f| f(annotation))
+            .unwrap_or_default()
+    }
+
+    /// Fills the remaining width of a line that contains a horizontal rule with the rule
+    /// character.
+    fn fill_rule(&self, line: &mut Line, width: usize) {
+        let char_width = self.hr_char.width().unwrap_or_default().max(1);
+        let n = width.saturating_sub(line.width()) / char_width;
+        let rule = self.hr_char.to_string().repeat(n);
+        line.elements.push((Element::plain(rule), false));
+    }
+
+    /// Replaces the non-breaking spaces with regular spaces and removes the soft hyphens in the
+    /// given line, except for a soft hyphen at the end of the line that is rendered as a hyphen,
+    /// see [`set_normalize_spaces`][].
+    ///
+    /// [`set_normalize_spaces`]: #method.set_normalize_spaces
+    fn normalize_line(&self, line: &mut Line) {
+        if !self.normalize_spaces {
+            return;
+        }
+        let last = line
+            .elements
+            .iter()
+            .rposition(|(element, _)| !element.text.is_empty());
+        for (idx, (element, _)) in line.elements.iter_mut().enumerate() {
+            if element.text.contains(&['\u{a0}', SOFT_HYPHEN][..]) {
+                let mut text: String = element
+                    .text
+                    .chars()
+                    .filter(|&c| c != SOFT_HYPHEN)
+                    .map(|c| if c == '\u{a0}' { ' ' } else { c })
+                    .collect();
+                if Some(idx) == last && element.text.ends_with(SOFT_HYPHEN) {
+                    text.push('-');
+                }
+                element.text = text;
+            }
+        }
+    }
+
+    /// Applies the emoji mode and the control character display to the given text.
+    ///
+    /// The spaces are normalized later, see `normalize_line`.
+    fn clean_text(&self, text: &str) -> String {
+        let text: std::borrow::Cow<'_, str> = match self.emoji_mode {
+            EmojiMode::Unicode => text.into(),
+            EmojiMode::Shortcode => emoji::replace(text, false).into(),
+            EmojiMode::Strip => emoji::replace(text, true).into(),
+        };
+        crate::replace_control_chars(&text, self.control_char_display).into_owned()
+    }
+
+    /// Returns the quotation marks for an inline quote with the given nesting depth.
+    fn quote_marks(&self, depth: usize) -> Option<&(String, String)> {
+        if self.quote_marks.is_empty() {
+            None
+        } else {
+            self.quote_marks.get(depth % self.quote_marks.len())
+        }
+    }
+
+    /// Applies the script rendering to text that is part of a subscript or superscript.
+    fn script_text(&self, text: &str, state: &mut RenderState) -> String {
+        let (prefix, map): (_, fn(char) -> Option<char>) = if state.sup_depth > 0 {
+            ('^', superscript)
+        } else if state.sub_depth > 0 {
+            ('_', subscript)
+        } else {
+            return text.to_owned();
+        };
+        let script_start = std::mem::replace(&mut state.script_start, false);
+
+        if self.script_rendering == ScriptRendering::Unicode {
+            let mapped: Option<String> = text
+                .chars()
+                .map(|c| if c == ' ' { Some(c) } else { map(c) })
+                .collect();
+            if let Some(mapped) = mapped {
+                return mapped;
+            }
+        }
+        if self.script_rendering != ScriptRendering::Plain && script_start {
+            format!("{}{}", prefix, text)
+        } else {
+            text.to_owned()
+        }
+    }
+
+    fn convert_line(
+        &self,
+        line: &text_renderer::TaggedLine<Vec<D::Annotation>>,
+        state: &mut RenderState,
+    ) -> Line {
+        let mut converted = Line::default();
+        // The number and the width of the last string in the line, used to find list markers.
+        let mut strings: usize = 0;
+        let mut last_width = 0;
+        // The landmarks that start before the next text, see `RenderState::pending_landmarks`.
+        let mut landmark_starts = std::mem::take(&mut state.pending_landmarks);
+        // The indices of the opening quotation marks that take the style of the next text.
+        let mut open_quotes = Vec::new();
+        for element in line.iter() {
+            let ts = match element {
+                text_renderer::TaggedLineElement::Str(ts) => {
+                    strings += 1;
+                    last_width = ts.s.width();
+                    converted.list_prefix = Some(state.list_markers.iter().sum())
+                        .filter(|_| !state.list_markers.is_empty());
+                    ts
+                }
+                text_renderer::TaggedLineElement::FragmentStart(fragment) => {
+                    match markers::Marker::parse(fragment) {
+                        Some(markers::Marker::HorizontalRule) => converted.rule = true,
+                        Some(markers::Marker::ListItemStart) => {
+                            // html2text adds one prefix per list level: the marker in the first
+                            // line of an item and spaces with the same width in all other lines.
+                            state.list_markers.truncate(strings.saturating_sub(1));
+                            state.list_markers.push(last_width);
+                            converted.list_item_start = true;
+                        }
+                        Some(markers::Marker::ListItemEnd) => {
+                            state.list_markers.pop();
+                        }
+                        Some(markers::Marker::CodeEnd) => {
+                            converted.code_end = Some(converted.elements.len());
+                        }
+                        Some(markers::Marker::HeadingStart(level)) => {
+                            converted.headings.push(state.headings.len());
+                            state.heading_prefix = converted.width();
+                            state.apply(markers::Marker::HeadingStart(level), &self.texts);
+                        }
+                        Some(markers::Marker::SummaryStart(open)) => {
+                            let toggle = if open {
+                                crate::SECTION_OPEN_MARKER
+                            } else {
+                                crate::SECTION_CLOSED_MARKER
+                            };
+                            let mut element = Element::plain(toggle.to_owned());
+                            element.set_section_toggle(Some(state.sections.len()));
+                            converted.elements.push((element, false));
+                            state.apply(markers::Marker::SummaryStart(open), &self.texts);
+                        }
+                        Some(markers::Marker::SummaryEnd) => {
+                            converted.summary_ends.extend(state.details.last());
+                        }
+                        Some(markers::Marker::DetailsEnd) => {
+                            converted.section_ends.extend(state.details.last());
+                            state.apply(markers::Marker::DetailsEnd, &self.texts);
+                        }
+                        Some(markers::Marker::LandmarkStart(kind)) => {
+                            landmark_starts.push(state.landmarks.len());
+                            state.apply(markers::Marker::LandmarkStart(kind), &self.texts);
+                        }
+                        Some(markers::Marker::LandmarkEnd) => {
+                            converted.landmark_ends.extend(state.open_landmarks.last());
+                            state.apply(markers::Marker::LandmarkEnd, &self.texts);
+                        }
+                        Some(markers::Marker::QuoteStart) => {
+                            if let Some((open, _)) = self.quote_marks(state.quote_depth) {
+                                open_quotes.push(converted.elements.len());
+                                converted
+                                    .elements
+                                    .push((Element::plain(open.clone()), false));
+                            }
+                            state.apply(markers::Marker::QuoteStart, &self.texts);
+                        }
+                        Some(markers::Marker::QuoteEnd) => {
+                            state.apply(markers::Marker::QuoteEnd, &self.texts);
+                            if let Some((_, close)) = self.quote_marks(state.quote_depth) {
+                                let element = match converted.elements.last() {
+                                    Some((last, _)) if open_quotes.is_empty() => {
+                                        quote_mark(close, last)
+                                    }
+                                    _ => Element::plain(close.clone()),
+                                };
+                                converted.elements.push((element, false));
+                            }
+                        }
+                        Some(markers::Marker::MathEnd) => {
+                            // Formulas without text content still show their alternative text.
+                            if let Some(alt_text) = state.math_alt_text.take() {
+                                if self.math_rendering == MathRendering::AltText {
+                                    let alt_text = self.clean_text(&alt_text);
+                                    let element = Element::styled(alt_text, self.math_style);
+                                    converted.elements.push((element, false));
+                                    converted.widened = true;
+                                }
+                            }
+                            state.apply(markers::Marker::MathEnd, &self.texts);
+                        }
+                        Some(marker) => state.apply(marker, &self.texts),
+                        None => converted.anchors.push(fragment.clone()),
+                    }
+                    continue;
+                }
+            };
+            let tags: Vec<_> = ts.tag.iter().filter(|a| !self.is_ignored(a)).collect();
+            let raw = restore_no_break_spaces(&ts.s, &mut state.literals);
+            let s = self.clean_text(&raw);
+            if s.width() > raw.width() {
+                converted.widened = true;
+            }
+            let text = if state.math_depth == 0 {
+                self.script_text(&s, state)
+            } else {
+                match self.math_rendering {
+                    MathRendering::Hidden => continue,
+                    MathRendering::AltText if state.math_has_alt_text => {
+                        match state.math_alt_text.take() {
+                            Some(alt_text) => {
+                                converted.widened = true;
+                                self.clean_text(&alt_text)
+                            }
+                            None => continue,
+                        }
+                    }
+                    _ => self.script_text(&s, state),
+                }
+            };
+            if converted.elements.is_empty() {
+                converted.continuation = tags
+                    .iter()
+                    .copied()
+                    .any(|a| self.converter.is_preformat_continuation(a));
+            }
+            if tags
+                .iter()
+                .copied()
+                .any(|a| self.converter.is_preformatted(a))
+            {
+                converted.preformatted = true;
+            }
+
+            let styles: Vec<_> = tags
+                .iter()
+                .copied()
+                .filter_map(|a| self.converter.get_style(a))
+                .collect();
+            let mut style = self.converter.merge_styles(&styles);
+            if state.term_depth > 0 {
+                style = style.combine(self.term_style);
+            }
+            if state.math_depth > 0 {
+                style = style.combine(self.math_style);
+            }
+            let link_target = tags
+                .iter()
+                .copied()
+                .find_map(|a| self.converter.get_link(a))
+                .filter(|target| !(self.skip_empty_links && target.is_empty()))
+                .map(ToOwned::to_owned);
+            let unbreakable = tags
+                .iter()
+                .copied()
+                .any(|a| self.converter.is_unbreakable(a));
+            if let Some(heading) = state.heading {
+                // Skip the line prefix, for example the `#` characters added by the decorator.
+                let mut column = converted.width();
+                let heading_text = &mut state.headings[heading].1;
+                if converted.elements.is_empty() && !heading_text.is_empty() {
+                    heading_text.push(' ');
+                }
+                for c in text.chars() {
+                    if column >= state.heading_prefix {
+                        heading_text.push(c);
+                    }
+                    column += c.width().unwrap_or_default();
+                }
+            }
+            let mut element = Element::new(text, style, link_target);
+            element.set_link_description(state.link_description.clone());
+            element.set_access_key(state.access_key);
+            if !element.text.trim().is_empty() {
+                converted.landmark_starts.append(&mut landmark_starts);
+            }
+            for idx in open_quotes.drain(..) {
+                let mark = &mut converted.elements[idx].0;
+                *mark = quote_mark(&mark.text, &element);
+            }
+            converted.elements.push((element, unbreakable));
+        }
+        state.pending_landmarks = landmark_starts;
+        converted
+    }
+}
+
+/// Returns an element with the given quotation mark and the style and link of the given element.
+fn quote_mark(mark: &str, element: &Element) -> Element {
+    let mut mark = Element::new(mark.to_owned(), element.style, element.link_target.clone());
+    mark.set_link_description(element.link_description.clone());
+    mark.set_access_key(element.access_key);
+    mark
+}
+
+/// The state of the elements that are tracked using markers while rendering a document.
+#[derive(Debug, Default)]
+struct RenderState {
+    term_depth: usize,
+    sub_depth: usize,
+    sup_depth: usize,
+    /// Whether the next text is the start of a subscript or superscript.
+    script_start: bool,
+    link_description: Option<String>,
+    access_key: Option<char>,
+    math_depth: usize,
+    quote_depth: usize,
+    /// The alternative text of the current formula if it has not been rendered yet.
+    math_alt_text: Option<String>,
+    math_has_alt_text: bool,
+    /// The level and text of all headings that have been started so far.
+    headings: Vec<(u8, String)>,
+    /// The index of the current heading in `headings`.
+    heading: Option<usize>,
+    /// The width of the line prefix of the current heading.
+    heading_prefix: usize,
+    /// Whether the `<details>` elements that have been started so far are open per default.
+    sections: Vec<bool>,
+    /// The indices of the current `<details>` elements in `sections`.
+    details: Vec<usize>,
+    /// The widths of the markers of the current list items, starting with the outermost list.
+    list_markers: Vec<usize>,
+    /// The kinds of the landmarks that have been started so far.
+    landmarks: Vec<LandmarkKind>,
+    /// The indices of the current landmarks in `landmarks`.
+    open_landmarks: Vec<usize>,
+    /// The indices of the landmarks whose start marker has not been followed by text yet.
+    ///
+    /// `html2text` attaches fragments that precede the first text of a block to the end of the
+    /// previous line, so we assign the start to the line of the next text instead.
+    pending_landmarks: Vec<usize>,
+    /// The number of literal `markers::NO_BREAK_SPACE` characters that have been announced by a
+    /// marker but not rendered yet.
+    literals: usize,
+}
+
+impl RenderState {
+    /// Applies the given marker.  The attribute texts of the document are used to resolve the
+    /// texts that are referenced by the marker.
+    fn apply(&mut self, marker: markers::Marker, texts: &[String]) {
+        match marker {
+            markers::Marker::TermStart => self.term_depth += 1,
+            markers::Marker::TermEnd => self.term_depth = self.term_depth.saturating_sub(1),
+            markers::Marker::HorizontalRule => {}
+            markers::Marker::SubscriptStart => {
+                self.sub_depth += 1;
+                self.script_start = true;
+            }
+            markers::Marker::SubscriptEnd => self.sub_depth = self.sub_depth.saturating_sub(1),
+            markers::Marker::SuperscriptStart => {
+                self.sup_depth += 1;
+                self.script_start = true;
+            }
+            markers::Marker::SuperscriptEnd => self.sup_depth = self.sup_depth.saturating_sub(1),
+            markers::Marker::LinkDescription(idx) => {
+                self.link_description = texts.get(idx).cloned();
+            }
+            markers::Marker::AccessKey(key) => self.access_key = Some(key),
+            markers::Marker::LinkEnd => {
+                self.link_description = None;
+                self.access_key = None;
+            }
+            markers::Marker::MathStart(alt_text) => {
+                self.math_depth += 1;
+                self.math_alt_text = alt_text.and_then(|idx| texts.get(idx).cloned());
+                self.math_has_alt_text = self.math_alt_text.is_some();
+            }
+            markers::Marker::HeadingStart(level) => {
+                self.heading = Some(self.headings.len());
+                self.headings.push((level, String::new()));
+            }
+            markers::Marker::HeadingEnd => self.heading = None,
+            markers::Marker::SummaryStart(open) => {
+                self.details.push(self.sections.len());
+                self.sections.push(open);
+            }
+            markers::Marker::SummaryEnd => {}
+            markers::Marker::DetailsEnd => {
+                self.details.pop();
+            }
+            markers::Marker::QuoteStart => self.quote_depth += 1,
+            markers::Marker::QuoteEnd => self.quote_depth = self.quote_depth.saturating_sub(1),
+            markers::Marker::ListItemStart | markers::Marker::ListItemEnd => {}
+            markers::Marker::CodeEnd => {}
+            markers::Marker::Literal => self.literals += 1,
+            markers::Marker::LandmarkStart(kind) => {
+                self.open_landmarks.push(self.landmarks.len());
+                self.landmarks.push(kind);
+            }
+            markers::Marker::LandmarkEnd => {
+                self.open_landmarks.pop();
+            }
+            markers::Marker::MathEnd => {
+                self.math_depth = self.math_depth.saturating_sub(1);
+                self.math_has_alt_text = false;
+                self.math_alt_text = None;
+            }
+        }
+    }
+}
+
+/// A line that has been converted from the `html2text` output but not yet added to the document.
+///
+/// The elements are stored together with a flag that indicates whether they are unbreakable, see
+/// [`Converter::is_unbreakable`][].
+///
+/// [`Converter::is_unbreakable`]: trait.Converter.html#method.is_unbreakable
+#[derive(Debug, Default)]
+struct Line {
+    elements: Vec<(Element, bool)>,
+    preformatted: bool,
+    continuation: bool,
+    rule: bool,
+    /// The column of the text of the list item that contains the last text of this line, if any.
+    list_prefix: Option<usize>,
+    /// Whether a list item starts in this line.
+    list_item_start: bool,
+    /// The number of elements before the end of the last `<code>` element in this line, if any.
+    code_end: Option<usize>,
+    /// Whether text has been replaced or inserted after wrapping, so that the line may be wider
+    /// than the render width.
+    widened: bool,
+    anchors: Vec<String>,
+    /// The indices of the headings that start in this line, see `RenderState::headings`.
+    headings: Vec<usize>,
+    /// The indices of the sections whose summary ends in this line, see `RenderState::sections`.
+    summary_ends: Vec<usize>,
+    /// The indices of the sections that end in this line.
+    section_ends: Vec<usize>,
+    /// The indices of the landmarks that start in this line, see `RenderState::landmarks`.
+    landmark_starts: Vec<usize>,
+    /// The indices of the landmarks that end in this line.
+    landmark_ends: Vec<usize>,
+}
+
+impl Line {
+    fn width(&self) -> usize {
+        self.elements.iter().map(|(e, _)| e.text.width()).sum()
+    }
+
+    /// Returns the width of the leading whitespace of this line, or `None` if it is blank.
+    fn indentation(&self) -> Option<usize> {
+        let mut indentation = 0;
+        for (element, _) in &self.elements {
+            let trimmed = element.text.trim_start_matches(' ');
+            indentation += element.text.len() - trimmed.len();
+            if !trimmed.is_empty() {
+                return Some(indentation);
+            }
+        }
+        None
+    }
+
+    /// Removes the given number of leading spaces from this line.
+    fn trim_start(&mut self, mut n: usize) {
+        while n > 0 && !self.elements.is_empty() {
+            let text = &mut self.elements[0].0.text;
+            let spaces = text.len() - text.trim_start_matches(' ').len();
+            let removed = std::cmp::min(spaces, n);
+            text.replace_range(..removed, "");
+            n -= removed;
+            if text.is_empty() {
+                self.elements.remove(0);
+            } else if removed == spaces {
+                break;
+            }
+        }
+    }
+
+    /// Removes trailing whitespace that has been left at the end of a wrapped line.
+    ///
+    /// Otherwise the trailing whitespace would be included in the width of the document, so it
+    /// could exceed the available width.
+    fn trim_end(&mut self) {
+        while let Some((element, _)) = self.elements.last_mut() {
+            if element.link_target.is_some() {
+                break;
+            }
+            let trimmed = element.text.trim_end().len();
+            element.text.truncate(trimmed);
+            if element.text.is_empty() {
+                self.elements.pop();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+/// Applies the given background color to the elements of a line and pads it to the given width.
+fn fill_background(line: &mut Line, width: usize, background: theme::Color) {
+    let back = theme::ColorStyle::back(background);
+    for (element, _) in &mut line.elements {
+        element.style = element.style.combine(back);
+    }
+    let n = width.saturating_sub(line.width());
+    if n > 0 {
+        line.elements
+            .push((Element::styled(" ".repeat(n), back.into()), false));
+    }
+}
+
+/// Removes the common leading whitespace from all lines.
+///
+/// If `include_preformatted` is false, preformatted lines are neither considered when
+/// determining the common indentation nor trimmed.
+fn trim_left_margin(lines: &mut [Line], include_preformatted: bool) {
+    let is_included = |line: &Line| include_preformatted || !line.preformatted;
+    let margin = lines
+        .iter()
+        .filter(|line| is_included(line))
+        .filter_map(Line::indentation)
+        .min()
+        .unwrap_or_default();
+    if margin > 0 {
+        for line in lines.iter_mut().filter(|line| is_included(line)) {
+            line.trim_start(margin);
+        }
+    }
+}
+
+/// Moves unbreakable runs that have been split at the end of a line to the next line.
+///
+/// A run is only moved if the next line starts with the rest of the run and if the joined line
+/// still fits into the given width.
+fn keep_runs_together(lines: &mut [Line], width: usize) {
+    for i in 1..lines.len() {
+        let (head, tail) = lines.split_at_mut(i);
+        let prev_code_end = head[i - 1].code_end;
+        let prev = &mut head[i - 1].elements;
+        let next = &mut tail[0];
+
+        if !next
+            .elements
+            .first()
+            .map(|(_, unbreakable)| *unbreakable)
+            .unwrap_or_default()
+        {
+            continue;
+        }
+        let run_start = prev
+            .iter()
+            .rposition(|(_, unbreakable)| !unbreakable)
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        // The run at the end of the line is complete if a code element ends after it, so the
+        // next line starts with a different run.
+        if run_start == prev.len() || run_start == 0 || prev_code_end == Some(prev.len()) {
+            continue;
+        }
+
+        let run_width: usize = prev[run_start..].iter().map(|(e, _)| e.text.width()).sum();
+        if run_width + 1 + next.width() > width {
+            continue;
+        }
+
+        let mut run: Vec<_> = prev.drain(run_start..).collect();
+        if let Some((last, _)) = prev.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+        }
+        let style = run.last().map(|(e, _)| e.style).unwrap_or_default();
+        run.push((Element::styled(" ".to_owned(), style), true));
+        next.elements.splice(0..0, run);
+    }
+}
+
+/// Wraps the lines again that have become wider than the given width after wrapping, for example
+/// because of emoji shortcodes or the caret notation of control characters.
+///
+/// The continuation lines are indented like the first line.
+fn wrap_widened_lines(lines: Vec<Line>, width: usize) -> Vec<Line> {
+    let mut result = Vec::with_capacity(lines.len());
+    for mut first in lines {
+        let indent = first.indentation().unwrap_or_default();
+        if !first.widened || first.preformatted || first.width() <= width || indent >= width {
+            result.push(first);
+            continue;
+        }
+
+        let mut elements: Vec<_> = first.elements.drain(..).map(|(e, _)| e).collect();
+        let content = split_off_columns(&mut elements, indent);
+        let mut wrapper = crate::LineWrapper::new(width - indent);
+        for (idx, element) in content.into_iter().enumerate() {
+            wrapper.push(idx, element);
+        }
+        let mut summary_ends = std::mem::take(&mut first.summary_ends);
+        let mut section_ends = std::mem::take(&mut first.section_ends);
+        let mut landmark_ends = std::mem::take(&mut first.landmark_ends);
+        let list_prefix = first.list_prefix;
+        for (i, wrapped) in wrapper.finish().into_iter().enumerate() {
+            let wrapped = wrapped.into_iter().map(|e| (e, false));
+            if i == 0 {
+                first.elements = elements.drain(..).map(|e| (e, false)).collect();
+                first.elements.extend(wrapped);
+                result.push(std::mem::take(&mut first));
+            } else {
+                let mut line = Line {
+                    list_prefix,
+                    ..Default::default()
+                };
+                line.elements
+                    .push((Element::plain(" ".repeat(indent)), false));
+                line.elements.extend(wrapped);
+                result.push(line);
+            }
+        }
+        if let Some(last) = result.last_mut() {
+            last.summary_ends.append(&mut summary_ends);
+            last.section_ends.append(&mut section_ends);
+            last.landmark_ends.append(&mut landmark_ends);
+        }
+    }
+    result
+}
+
+/// Replaces the `markers::NO_BREAK_SPACE` characters in the given text with non-breaking spaces,
+/// except for the given number of literal characters that have been announced by markers.
+fn restore_no_break_spaces<'a>(text: &'a str, literals: &mut usize) -> std::borrow::Cow<'a, str> {
+    if !text.contains(markers::NO_BREAK_SPACE) {
+        return text.into();
+    }
+    text.chars()
+        .map(|c| match c {
+            markers::NO_BREAK_SPACE if *literals > 0 => {
+                *literals -= 1;
+                c
+            }
+            markers::NO_BREAK_SPACE => '\u{a0}',
+            c => c,
+        })
+        .collect::<String>()
+        .into()
+}
+
+/// Uses the soft hyphens in the first word of a wrapped line as wrap points.
+///
+/// `html2text` does not break lines at soft hyphens, so we move the longest part of the first
+/// word of a line that ends with a soft hyphen to the end of the previous line if it fits there,
+/// including the hyphen.
+fn hyphenate(lines: &mut [Line], width: usize) {
+    for idx in 1..lines.len() {
+        let (head, tail) = lines.split_at_mut(idx);
+        let (prev, next) = (&mut head[idx - 1], &mut tail[0]);
+        if prev.preformatted || next.preformatted || prev.rule || next.list_item_start {
+            continue;
+        }
+        let indent = match next.indentation() {
+            Some(indent) => indent,
+            None => continue,
+        };
+        let same_block = prev.indentation() == Some(indent)
+            || (prev.list_prefix == Some(indent) && next.list_prefix == Some(indent));
+        if !same_block || !is_wrapped(prev, next, width) {
+            continue;
+        }
+
+        let prev_width = prev
+            .elements
+            .iter()
+            .map(|(e, _)| e.text.as_str())
+            .collect::<String>()
+            .trim_end_matches(' ')
+            .width();
+        // The space before the word and the hyphen have to fit into the line.
+        let available = width.saturating_sub(prev_width + 2);
+        let text: String = next.elements.iter().map(|(e, _)| e.text.as_str()).collect();
+        let word_start = text.len() - text.trim_start_matches(' ').len();
+        let mut column = 0;
+        let mut split = None;
+        for (i, c) in text[word_start..].char_indices() {
+            if c == ' ' {
+                break;
+            } else if c == SOFT_HYPHEN {
+                if column > available {
+                    break;
+                }
+                split = Some(word_start + i + c.len_utf8());
+            } else {
+                column += c.width().unwrap_or_default();
+            }
+        }
+        let split = match split {
+            Some(split) if !text[split..].starts_with(' ') && split < text.len() => split,
+            _ => continue,
+        };
+
+        let mut elements: Vec<_> = next.elements.drain(..).map(|(e, _)| e).collect();
+        let rest = split_off_bytes(&mut elements, split);
+        let part = split_off_bytes(&mut elements, word_start);
+        next.elements = elements
+            .into_iter()
+            .chain(rest)
+            .map(|e| (e, false))
+            .collect();
+        prev.trim_end();
+        prev.elements.push((Element::plain(" ".to_owned()), false));
+        prev.elements.extend(part.into_iter().map(|e| (e, false)));
+    }
+}
+
+/// Splits the given elements at the given byte offset of their concatenated text and returns the
+/// elements after the split.
+fn split_off_bytes(elements: &mut Vec<Element>, mut offset: usize) -> Vec<Element> {
+    let mut idx = 0;
+    while idx < elements.len() && offset >= elements[idx].text.len() {
+        offset -= elements[idx].text.len();
+        idx += 1;
+    }
+    if idx < elements.len() && offset > 0 {
+        let rest = elements[idx].text.split_off(offset);
+        let element = Element {
+            text: rest,
+            ..elements[idx].clone()
+        };
+        elements.insert(idx + 1, element);
+        idx += 1;
+    }
+    elements.split_off(idx)
+}
+
+/// Wraps the paragraphs of list items again so that their continuation lines are indented by
+/// `LIST_HANGING_INDENT` columns more than the first line.
+///
+/// A paragraph consists of a line of a list item and the following lines of the same item that
+/// have been wrapped by `html2text`, i. e. the first word of the next line would not have fit
+/// into the previous line.
+fn indent_list_items(lines: Vec<Line>, width: usize) -> Vec<Line> {
+    let mut result = Vec::with_capacity(lines.len());
+    let mut lines = lines.into_iter().peekable();
+    while let Some(first) = lines.next() {
+        let prefix = match first.list_prefix {
+            Some(prefix) if !first.preformatted && prefix + LIST_HANGING_INDENT < width => prefix,
+            _ => {
+                result.push(first);
+                continue;
+            }
+        };
+        let mut paragraph = vec![first];
+        while let Some(next) = lines.peek() {
+            let prev = &paragraph[paragraph.len() - 1];
+            let is_continuation = next.list_prefix == Some(prefix)
+                && !next.list_item_start
+                && !next.preformatted
+                && next.indentation() == Some(prefix)
+                && is_wrapped(prev, next, width);
+            if !is_continuation {
+                break;
+            }
+            paragraph.extend(lines.next());
+        }
+        if paragraph.len() == 1 {
+            result.append(&mut paragraph);
+        } else {
+            result.append(&mut wrap_with_hanging_indent(paragraph, prefix, width));
+        }
+    }
+    result
+}
+
+/// Returns whether the given line has been wrapped before the next line, i. e. whether the first
+/// word of the next line would not fit into the line.
+fn is_wrapped(line: &Line, next: &Line, width: usize) -> bool {
+    let text: String = line.elements.iter().map(|(e, _)| e.text.as_str()).collect();
+    let next_text: String = next.elements.iter().map(|(e, _)| e.text.as_str()).collect();
+    let word = next_text
+        .trim_start_matches(' ')
+        .split(' ')
+        .next()
+        .unwrap_or_default();
+    text.trim_end_matches(' ').width() + 1 + word.width() > width
+}
+
+/// Wraps the text of the given lines of a list item paragraph again with a hanging indent.
+///
+/// The text of the item starts at the given column in all lines.
+fn wrap_with_hanging_indent(paragraph: Vec<Line>, prefix: usize, width: usize) -> Vec<Line> {
+    let mut head = Line::default();
+    let mut tail = Line::default();
+    let mut content: Vec<Element> = Vec::new();
+    for (i, mut line) in paragraph.into_iter().enumerate() {
+        let mut elements: Vec<_> = line.elements.drain(..).map(|(e, _)| e).collect();
+        let mut text = split_off_columns(&mut elements, prefix);
+        if i == 0 {
+            head = line;
+            head.elements = elements.into_iter().map(|e| (e, false)).collect();
+        } else {
+            if let (Some(last), Some(next)) = (content.last(), text.first()) {
+                // Keep links together if they have been split at the wrap point.
+                let space = if last.link_target.is_some() && last.link_target == next.link_target {
+                    Element {
+                        text: " ".to_owned(),
+                        ..last.clone()
+                    }
+                } else {
+                    Element::plain(" ".to_owned())
+                };
+                content.push(space);
+            }
+            head.anchors.append(&mut line.anchors);
+            head.headings.append(&mut line.headings);
+            head.landmark_starts.append(&mut line.landmark_starts);
+            tail.summary_ends.append(&mut line.summary_ends);
+            tail.section_ends.append(&mut line.section_ends);
+            tail.landmark_ends.append(&mut line.landmark_ends);
+        }
+        if let Some(last) = text.last_mut() {
+            let trimmed = last.text.trim_end_matches(' ').len();
+            last.text.truncate(trimmed);
+        }
+        content.append(&mut text);
+    }
+    head.summary_ends.append(&mut tail.summary_ends);
+    head.section_ends.append(&mut tail.section_ends);
+    head.landmark_ends.append(&mut tail.landmark_ends);
+
+    let indent = prefix + LIST_HANGING_INDENT;
+    let mut wrapper = crate::LineWrapper::new(width - indent).with_first_width(width - prefix);
+    for (idx, element) in content.into_iter().enumerate() {
+        wrapper.push(idx, element);
+    }
+    let mut lines = Vec::new();
+    for (i, elements) in wrapper.finish().into_iter().enumerate() {
+        let elements = elements.into_iter().map(|e| (e, false));
+        if i == 0 {
+            head.elements.extend(elements);
+            lines.push(std::mem::take(&mut head));
+        } else {
+            let mut line = Line::default();
+            line.elements
+                .push((Element::plain(" ".repeat(indent)), false));
+            line.elements.extend(elements);
+            lines.push(line);
+        }
+    }
+    if let Some(last) = lines.last_mut() {
+        last.summary_ends.append(&mut head.summary_ends);
+        last.section_ends.append(&mut head.section_ends);
+        last.landmark_ends.append(&mut head.landmark_ends);
+    }
+    lines
+}
+
+/// Splits the given elements after the given number of columns and returns the elements after
+/// the split.
+fn split_off_columns(elements: &mut Vec<Element>, mut columns: usize) -> Vec<Element> {
+    let mut idx = 0;
+    while columns > 0 && idx < elements.len() {
+        let width = elements[idx].text.width();
+        if width <= columns {
+            columns -= width;
+            idx += 1;
+            continue;
+        }
+        let mut split = elements[idx].text.len();
+        let mut column = 0;
+        for (i, c) in elements[idx].text.char_indices() {
+            if column >= columns {
+                split = i;
+                break;
+            }
+            column += c.width().unwrap_or_default();
+        }
+        let rest = elements[idx].text.split_off(split);
+        let element = Element {
+            text: rest,
+            ..elements[idx].clone()
+        };
+        elements.insert(idx + 1, element);
+        idx += 1;
+        break;
+    }
+    elements.split_off(idx)
+}
+
+/// Returns the Unicode superscript variant of the given character, if it exists.
+fn superscript(c: char) -> Option<char> {
+    let sup = match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        _ => return None,
+    };
+    Some(sup)
+}
+
+/// Returns the Unicode subscript variant of the given character, if it exists.
+fn subscript(c: char) -> Option<char> {
+    let sub = match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    };
+    Some(sub)
 }
 
 impl Converter<text_renderer::RichAnnotation> for RichConverter {
@@ -142,6 +2176,22 @@ impl Converter<text_renderer::RichAnnotation> for RichConverter {
         }
     }
 
+    fn is_unbreakable(&self, annotation: &text_renderer::RichAnnotation) -> bool {
+        use text_renderer::RichAnnotation;
+        matches!(
+            annotation,
+            RichAnnotation::Code | RichAnnotation::Preformat(_)
+        )
+    }
+
+    fn is_preformat_continuation(&self, annotation: &text_renderer::RichAnnotation) -> bool {
+        matches!(annotation, text_renderer::RichAnnotation::Preformat(true))
+    }
+
+    fn is_preformatted(&self, annotation: &text_renderer::RichAnnotation) -> bool {
+        matches!(annotation, text_renderer::RichAnnotation::Preformat(_))
+    }
+
     fn get_link<'a>(&self, annotation: &'a text_renderer::RichAnnotation) -> Option<&'a str> {
         if let text_renderer::RichAnnotation::Link(target) = annotation {
             Some(target)
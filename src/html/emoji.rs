@@ -0,0 +1,279 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Replacement of emoji with text shortcodes.
+//!
+//! Emoji can consist of multiple code points:  a base character can be followed by a variation
+//! selector or a skin tone modifier, several emoji can be joined with zero-width joiners, and
+//! flags consist of two regional indicators.  We treat such a sequence as one emoji and replace
+//! it with the shortcode of the longest known prefix.
+
+const VARIATION_SELECTOR: char = '\u{fe0f}';
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+/// The shortcodes of common emoji, based on the names used by GitHub.
+///
+/// Sequences must be listed before their prefixes.
+const SHORTCODES: &[(&str, &str)] = &[
+    ("👨\u{200d}💻", "man_technologist"),
+    ("👩\u{200d}💻", "woman_technologist"),
+    ("🏳\u{fe0f}\u{200d}🌈", "rainbow_flag"),
+    ("❤\u{fe0f}\u{200d}🔥", "heart_on_fire"),
+    ("😀", "grinning"),
+    ("😃", "smiley"),
+    ("😄", "smile"),
+    ("😁", "grin"),
+    ("😆", "laughing"),
+    ("😅", "sweat_smile"),
+    ("😂", "joy"),
+    ("🤣", "rofl"),
+    ("🙂", "slightly_smiling_face"),
+    ("🙃", "upside_down_face"),
+    ("😉", "wink"),
+    ("😊", "blush"),
+    ("😇", "innocent"),
+    ("😍", "heart_eyes"),
+    ("😘", "kissing_heart"),
+    ("😋", "yum"),
+    ("😜", "stuck_out_tongue_winking_eye"),
+    ("🤔", "thinking"),
+    ("😐", "neutral_face"),
+    ("😑", "expressionless"),
+    ("😏", "smirk"),
+    ("😒", "unamused"),
+    ("🙄", "roll_eyes"),
+    ("😬", "grimacing"),
+    ("😌", "relieved"),
+    ("😔", "pensive"),
+    ("😴", "sleeping"),
+    ("😷", "mask"),
+    ("🤯", "exploding_head"),
+    ("🥳", "partying_face"),
+    ("😎", "sunglasses"),
+    ("😕", "confused"),
+    ("😟", "worried"),
+    ("😮", "open_mouth"),
+    ("😲", "astonished"),
+    ("😳", "flushed"),
+    ("😢", "cry"),
+    ("😭", "sob"),
+    ("😱", "scream"),
+    ("😞", "disappointed"),
+    ("😤", "triumph"),
+    ("😡", "rage"),
+    ("😠", "angry"),
+    ("💀", "skull"),
+    ("💩", "hankey"),
+    ("🤖", "robot"),
+    ("🙈", "see_no_evil"),
+    ("🙉", "hear_no_evil"),
+    ("🙊", "speak_no_evil"),
+    ("👋", "wave"),
+    ("👌", "ok_hand"),
+    ("✌", "v"),
+    ("🤞", "crossed_fingers"),
+    ("👉", "point_right"),
+    ("👈", "point_left"),
+    ("👆", "point_up_2"),
+    ("👇", "point_down"),
+    ("👍", "+1"),
+    ("👎", "-1"),
+    ("👏", "clap"),
+    ("🙌", "raised_hands"),
+    ("🙏", "pray"),
+    ("💪", "muscle"),
+    ("👀", "eyes"),
+    ("🤷", "shrug"),
+    ("🤦", "facepalm"),
+    ("❤", "heart"),
+    ("💔", "broken_heart"),
+    ("💯", "100"),
+    ("💥", "boom"),
+    ("💬", "speech_balloon"),
+    ("💡", "bulb"),
+    ("🔥", "fire"),
+    ("✨", "sparkles"),
+    ("⭐", "star"),
+    ("🌟", "star2"),
+    ("⚡", "zap"),
+    ("☀", "sunny"),
+    ("🌈", "rainbow"),
+    ("🎉", "tada"),
+    ("🎁", "gift"),
+    ("🏆", "trophy"),
+    ("🚀", "rocket"),
+    ("🚧", "construction"),
+    ("✅", "white_check_mark"),
+    ("✔", "heavy_check_mark"),
+    ("❌", "x"),
+    ("❓", "question"),
+    ("❗", "exclamation"),
+    ("⚠", "warning"),
+    ("⛔", "no_entry"),
+    ("🔒", "lock"),
+    ("🔑", "key"),
+    ("🔗", "link"),
+    ("📌", "pushpin"),
+    ("📎", "paperclip"),
+    ("📝", "memo"),
+    ("📚", "books"),
+    ("📦", "package"),
+    ("📈", "chart_with_upwards_trend"),
+    ("📉", "chart_with_downwards_trend"),
+    ("📅", "date"),
+    ("📧", "email"),
+    ("💻", "computer"),
+    ("🔧", "wrench"),
+    ("🔨", "hammer"),
+    ("⚙", "gear"),
+    ("🐛", "bug"),
+    ("🦀", "crab"),
+    ("🐍", "snake"),
+    ("🐧", "penguin"),
+    ("🐶", "dog"),
+    ("🐱", "cat"),
+    ("🍕", "pizza"),
+    ("🍺", "beer"),
+    ("☕", "coffee"),
+    ("🌍", "earth_africa"),
+    ("🌎", "earth_americas"),
+    ("🌏", "earth_asia"),
+];
+
+/// The ranges of the supplementary characters that are displayed as emoji per default, i. e. that
+/// have the `Emoji_Presentation` property.
+///
+/// The other characters in these blocks, for example enclosed alphanumerics like `🅰` or
+/// mahjong tiles, are only displayed as emoji if they are followed by a variation selector.
+const EMOJI_PRESENTATION: &[(char, char)] = &[
+    ('\u{1f004}', '\u{1f004}'),
+    ('\u{1f0cf}', '\u{1f0cf}'),
+    ('\u{1f18e}', '\u{1f18e}'),
+    ('\u{1f191}', '\u{1f19a}'),
+    ('\u{1f1e6}', '\u{1f1ff}'),
+    ('\u{1f201}', '\u{1f201}'),
+    ('\u{1f21a}', '\u{1f21a}'),
+    ('\u{1f22f}', '\u{1f22f}'),
+    ('\u{1f232}', '\u{1f236}'),
+    ('\u{1f238}', '\u{1f23a}'),
+    ('\u{1f250}', '\u{1f251}'),
+    ('\u{1f300}', '\u{1f320}'),
+    ('\u{1f32d}', '\u{1f335}'),
+    ('\u{1f337}', '\u{1f37c}'),
+    ('\u{1f37e}', '\u{1f393}'),
+    ('\u{1f3a0}', '\u{1f3ca}'),
+    ('\u{1f3cf}', '\u{1f3d3}'),
+    ('\u{1f3e0}', '\u{1f3f0}'),
+    ('\u{1f3f4}', '\u{1f3f4}'),
+    ('\u{1f3f8}', '\u{1f43e}'),
+    ('\u{1f440}', '\u{1f440}'),
+    ('\u{1f442}', '\u{1f4fc}'),
+    ('\u{1f4ff}', '\u{1f53d}'),
+    ('\u{1f54b}', '\u{1f54e}'),
+    ('\u{1f550}', '\u{1f567}'),
+    ('\u{1f57a}', '\u{1f57a}'),
+    ('\u{1f595}', '\u{1f596}'),
+    ('\u{1f5a4}', '\u{1f5a4}'),
+    ('\u{1f5fb}', '\u{1f64f}'),
+    ('\u{1f680}', '\u{1f6c5}'),
+    ('\u{1f6cc}', '\u{1f6cc}'),
+    ('\u{1f6d0}', '\u{1f6d2}'),
+    ('\u{1f6d5}', '\u{1f6d7}'),
+    ('\u{1f6dc}', '\u{1f6df}'),
+    ('\u{1f6eb}', '\u{1f6ec}'),
+    ('\u{1f6f4}', '\u{1f6fc}'),
+    ('\u{1f7e0}', '\u{1f7eb}'),
+    ('\u{1f7f0}', '\u{1f7f0}'),
+    ('\u{1f90c}', '\u{1f93a}'),
+    ('\u{1f93c}', '\u{1f945}'),
+    ('\u{1f947}', '\u{1f9ff}'),
+    ('\u{1fa70}', '\u{1fa7c}'),
+    ('\u{1fa80}', '\u{1fa89}'),
+    ('\u{1fa8f}', '\u{1fac6}'),
+    ('\u{1face}', '\u{1fadc}'),
+    ('\u{1fadf}', '\u{1fae9}'),
+    ('\u{1faf0}', '\u{1faf8}'),
+];
+
+/// Returns whether the given character is an emoji that is not part of the table.
+///
+/// This covers the pictographic symbols that are displayed as emoji per default, see
+/// `EMOJI_PRESENTATION`.  Other symbols are only treated as emoji if they are followed by a
+/// variation selector.
+fn is_emoji(c: char) -> bool {
+    EMOJI_PRESENTATION
+        .iter()
+        .any(|&(start, end)| start <= c && c <= end)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+fn is_modifier(c: char) -> bool {
+    c == VARIATION_SELECTOR || ('\u{1f3fb}'..='\u{1f3ff}').contains(&c)
+}
+
+/// Replaces the emoji in the given text with their shortcodes, or removes them if `strip` is set.
+///
+/// Emoji that are not contained in the table are replaced with their code point, for example
+/// `:U+1F9A0:`, and flags are replaced with the country code, for example `:flag_de:`.
+pub fn replace(text: &str, strip: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match emoji(rest) {
+            Some((len, shortcode)) => {
+                if !strip {
+                    result.push(':');
+                    result.push_str(&shortcode);
+                    result.push(':');
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    result
+}
+
+/// Returns the length and the shortcode of the emoji sequence at the start of the given text.
+fn emoji(text: &str) -> Option<(usize, String)> {
+    let mut chars = text.chars();
+    let first = chars.next()?;
+    let second = chars.next();
+
+    let (mut len, shortcode) = if let Some((sequence, shortcode)) = SHORTCODES
+        .iter()
+        .find(|(sequence, _)| text.starts_with(sequence))
+    {
+        (sequence.len(), (*shortcode).to_owned())
+    } else if is_regional_indicator(first) && second.map(is_regional_indicator) == Some(true) {
+        let code: String = [first, second?]
+            .iter()
+            .map(|&c| (b'a' + (c as u32 - 0x1f1e6) as u8) as char)
+            .collect();
+        (first.len_utf8() * 2, format!("flag_{}", code))
+    } else if is_emoji(first) || second == Some(VARIATION_SELECTOR) {
+        (first.len_utf8(), format!("U+{:04X}", first as u32))
+    } else {
+        return None;
+    };
+
+    // Skip the modifiers and the joined emoji that are not part of the matched sequence.
+    loop {
+        let mut chars = text[len..].chars();
+        match chars.next() {
+            Some(c) if is_modifier(c) => len += c.len_utf8(),
+            Some(ZERO_WIDTH_JOINER) => match chars.next() {
+                Some(c) => len += ZERO_WIDTH_JOINER.len_utf8() + c.len_utf8(),
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    Some((len, shortcode))
+}
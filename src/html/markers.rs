@@ -0,0 +1,538 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! Markers for HTML elements that are not preserved by `html2text`.
+//!
+//! `html2text` only keeps the information that is needed to render the elements it knows about.
+//! To be able to handle other elements, we parse the document before passing it to `html2text`
+//! and insert empty elements with a special `id` attribute at the start and at the end of the
+//! elements we are interested in.  `html2text` renders these ids as zero-width fragment markers
+//! that the renderer can detect in the rendered lines.
+//!
+//! Attribute values that are needed by the renderer, for example the `aria-label` of links, are
+//! not embedded in the ids.  Instead, they are collected in a separate list, and the markers only
+//! contain their index in this list.
+
+use std::cell;
+use std::rc;
+
+use html5ever::tendril::TendrilSink as _;
+use html5ever::{local_name, namespace_url, ns};
+use markup5ever_rcdom::{Handle, Node, NodeData, RcDom, SerializableHandle};
+
+use crate::LandmarkKind;
+
+const PREFIX: &str = "cursive-markup:";
+
+/// The character that replaces non-breaking spaces in the text nodes.
+///
+/// `html2text` treats non-breaking spaces like regular spaces and wraps lines at them.  We use a
+/// character from the private use area that has the same width instead so that the renderer can
+/// restore the non-breaking spaces after wrapping.  If the document already contains this
+/// character, each occurrence is preceded by a [`Marker::Literal`][] marker.
+pub const NO_BREAK_SPACE: char = '\u{f8ff}';
+
+/// A marker for the start or the end of an HTML element.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Marker {
+    /// The start of a `<dt>` element.
+    TermStart,
+    /// The end of a `<dt>` element.
+    TermEnd,
+    /// A `<hr>` element.
+    HorizontalRule,
+    /// The start of a `<sub>` element.
+    SubscriptStart,
+    /// The end of a `<sub>` element.
+    SubscriptEnd,
+    /// The start of a `<sup>` element.
+    SuperscriptStart,
+    /// The end of a `<sup>` element.
+    SuperscriptEnd,
+    /// The index of the `aria-label` attribute of a link in the attribute texts, inserted at the
+    /// start of the `<a>` element.
+    LinkDescription(usize),
+    /// The `accesskey` attribute of a link, inserted at the start of the `<a>` element.
+    AccessKey(char),
+    /// The end of an `<a>` element with a description or an access key.
+    LinkEnd,
+    /// The start of a `<math>` element with the index of its `alttext` attribute in the
+    /// attribute texts.
+    MathStart(Option<usize>),
+    /// The end of a `<math>` element.
+    MathEnd,
+    /// The start of a heading (`<h1>` to `<h6>`) with the given level.
+    HeadingStart(u8),
+    /// The end of a heading.
+    HeadingEnd,
+    /// The start of the `<summary>` element of a `<details>` element that is open if the flag
+    /// is set.
+    SummaryStart(bool),
+    /// The end of a `<summary>` element.
+    SummaryEnd,
+    /// The end of a `<details>` element with a `<summary>` element.
+    DetailsEnd,
+    /// The start of a `<q>` element.
+    QuoteStart,
+    /// The end of a `<q>` element.
+    QuoteEnd,
+    /// The start of a `<li>` element, inserted after the list marker.
+    ListItemStart,
+    /// The end of a `<li>` element.
+    ListItemEnd,
+    /// The start of a landmark element, inserted before its first text.
+    LandmarkStart(LandmarkKind),
+    /// The end of a landmark element, inserted after its last text.
+    LandmarkEnd,
+    /// A [`NO_BREAK_SPACE`][] character from the source document, inserted directly before it.
+    Literal,
+    /// The end of a `<code>` element, inserted after the element.
+    CodeEnd,
+}
+
+impl Marker {
+    /// Parses a marker from the name of a fragment.
+    pub fn parse(fragment: &str) -> Option<Marker> {
+        let name = fragment.strip_prefix(PREFIX)?;
+        if let Some(idx) = name.strip_prefix("aria-label:") {
+            return idx.parse().ok().map(Marker::LinkDescription);
+        }
+        if let Some(key) = name.strip_prefix("accesskey:") {
+            return key.chars().next().map(Marker::AccessKey);
+        }
+        if let Some(level) = name.strip_prefix("h-start:") {
+            return level.parse().ok().map(Marker::HeadingStart);
+        }
+        if let Some(element) = name.strip_prefix("landmark-start:") {
+            return landmark_kind(element).map(Marker::LandmarkStart);
+        }
+        if let Some(idx) = name.strip_prefix("math-start:") {
+            return idx.parse().ok().map(|idx| Marker::MathStart(Some(idx)));
+        }
+        match name {
+            "dt-start" => Some(Marker::TermStart),
+            "dt-end" => Some(Marker::TermEnd),
+            "hr" => Some(Marker::HorizontalRule),
+            "sub-start" => Some(Marker::SubscriptStart),
+            "sub-end" => Some(Marker::SubscriptEnd),
+            "sup-start" => Some(Marker::SuperscriptStart),
+            "sup-end" => Some(Marker::SuperscriptEnd),
+            "a-end" => Some(Marker::LinkEnd),
+            "math-start" => Some(Marker::MathStart(None)),
+            "math-end" => Some(Marker::MathEnd),
+            "h-end" => Some(Marker::HeadingEnd),
+            "summary-start" => Some(Marker::SummaryStart(false)),
+            "summary-start:open" => Some(Marker::SummaryStart(true)),
+            "summary-end" => Some(Marker::SummaryEnd),
+            "details-end" => Some(Marker::DetailsEnd),
+            "q-start" => Some(Marker::QuoteStart),
+            "q-end" => Some(Marker::QuoteEnd),
+            "li-start" => Some(Marker::ListItemStart),
+            "li-end" => Some(Marker::ListItemEnd),
+            "landmark-end" => Some(Marker::LandmarkEnd),
+            "literal" => Some(Marker::Literal),
+            "code-end" => Some(Marker::CodeEnd),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> String {
+        let name = match self {
+            Marker::LinkDescription(idx) => return format!("{}aria-label:{}", PREFIX, idx),
+            Marker::AccessKey(key) => return format!("{}accesskey:{}", PREFIX, key),
+            Marker::MathStart(Some(idx)) => return format!("{}math-start:{}", PREFIX, idx),
+            Marker::HeadingStart(level) => return format!("{}h-start:{}", PREFIX, level),
+            Marker::LandmarkStart(kind) => {
+                return format!("{}landmark-start:{}", PREFIX, landmark_element(*kind))
+            }
+            Marker::MathStart(None) => "math-start",
+            Marker::MathEnd => "math-end",
+            Marker::HeadingEnd => "h-end",
+            Marker::SummaryStart(false) => "summary-start",
+            Marker::SummaryStart(true) => "summary-start:open",
+            Marker::SummaryEnd => "summary-end",
+            Marker::DetailsEnd => "details-end",
+            Marker::QuoteStart => "q-start",
+            Marker::QuoteEnd => "q-end",
+            Marker::ListItemStart => "li-start",
+            Marker::ListItemEnd => "li-end",
+            Marker::LandmarkEnd => "landmark-end",
+            Marker::Literal => "literal",
+            Marker::CodeEnd => "code-end",
+            Marker::TermStart => "dt-start",
+            Marker::TermEnd => "dt-end",
+            Marker::HorizontalRule => "hr",
+            Marker::SubscriptStart => "sub-start",
+            Marker::SubscriptEnd => "sub-end",
+            Marker::SuperscriptStart => "sup-start",
+            Marker::SuperscriptEnd => "sup-end",
+            Marker::LinkEnd => "a-end",
+        };
+        format!("{}{}", PREFIX, name)
+    }
+}
+
+/// Parses the given HTML document, inserts the markers and serializes the document again.
+///
+/// Returns the serialized document and the attribute texts that are referenced by the markers.
+pub fn insert(html: &str) -> (String, Vec<String>) {
+    let dom = html5ever::parse_document(RcDom::default(), Default::default()).one(html);
+    let mut texts = Vec::new();
+    visit(&dom.document, &mut texts);
+
+    let mut bytes = Vec::new();
+    let handle = SerializableHandle::from(dom.document.clone());
+    if html5ever::serialize(&mut bytes, &handle, Default::default()).is_err() {
+        return (html.to_owned(), Vec::new());
+    }
+    match String::from_utf8(bytes) {
+        Ok(html) => (html, texts),
+        Err(_) => (html.to_owned(), Vec::new()),
+    }
+}
+
+fn visit(handle: &Handle, texts: &mut Vec<String>) {
+    if let NodeData::Text { contents } = &handle.data {
+        let mut contents = contents.borrow_mut();
+        if contents.contains('\u{a0}') {
+            let replaced = contents.replace('\u{a0}', &NO_BREAK_SPACE.to_string());
+            *contents = replaced.into();
+        }
+        return;
+    }
+    // Ids with our prefix in the source document would be mistaken for markers.  The markers we
+    // insert ourselves are never visited, so we can safely remove all of them.
+    if let NodeData::Element { attrs, .. } = &handle.data {
+        attrs
+            .borrow_mut()
+            .retain(|attr| &*attr.name.local != "id" || !attr.value.starts_with(PREFIX));
+    }
+    let raw_text = is_element(handle, "script") || is_element(handle, "style");
+    let children = std::mem::take(&mut *handle.children.borrow_mut());
+    let mut visited = Vec::with_capacity(children.len());
+    for mut child in children {
+        if !raw_text && has_literals(&child) {
+            for node in mark_literals(handle, &child) {
+                if marker_of(&node).is_none() {
+                    visit(&node, texts);
+                }
+                visited.push(node);
+            }
+            continue;
+        }
+        visit(&child, texts);
+        if is_element(&child, "hr") {
+            child = replace_with_marker(handle, &child, &Marker::HorizontalRule);
+        } else if is_element(&child, "details") {
+            child = replace_with_block(handle, &child, local_name!("div"));
+        }
+        let is_code = is_element(&child, "code");
+        visited.push(child);
+        if is_code {
+            visited.push(marker_node(handle, &Marker::CodeEnd));
+        }
+    }
+    *handle.children.borrow_mut() = visited;
+
+    if is_element(handle, "dt") {
+        wrap_children(handle, Marker::TermStart, Marker::TermEnd);
+    } else if is_element(handle, "sub") {
+        wrap_children(handle, Marker::SubscriptStart, Marker::SubscriptEnd);
+    } else if is_element(handle, "sup") {
+        wrap_children(handle, Marker::SuperscriptStart, Marker::SuperscriptEnd);
+    } else if is_element(handle, "q") {
+        wrap_children(handle, Marker::QuoteStart, Marker::QuoteEnd);
+    } else if let Some(level) = heading_level(handle) {
+        wrap_children(handle, Marker::HeadingStart(level), Marker::HeadingEnd);
+    } else if is_element(handle, "math") {
+        let alt_text = attribute(handle, "alttext")
+            .filter(|alt_text| !alt_text.is_empty())
+            .map(|alt_text| push_text(texts, alt_text));
+        wrap_children(handle, Marker::MathStart(alt_text), Marker::MathEnd);
+    } else if is_element(handle, "details") {
+        let mut children = handle.children.borrow_mut();
+        if let Some(summary) = children
+            .iter_mut()
+            .find(|child| is_element(child, "summary"))
+        {
+            let open = attribute(handle, "open").is_some();
+            wrap_children(summary, Marker::SummaryStart(open), Marker::SummaryEnd);
+            *summary = replace_with_block(handle, summary, local_name!("p"));
+            children.push(marker_node(handle, &Marker::DetailsEnd));
+        }
+    } else if let Some(kind) = element_name(handle).and_then(landmark_kind) {
+        insert_around_text(handle, Marker::LandmarkStart(kind), Marker::LandmarkEnd);
+    } else if is_element(handle, "li") {
+        let start = marker_node(handle, &Marker::ListItemStart);
+        handle.children.borrow_mut().insert(0, start);
+        let end = marker_node(handle, &Marker::ListItemEnd);
+        handle.children.borrow_mut().push(end);
+        // html2text drops the fragments of list items, so we move the id into the item.
+        if let Some(id) = attribute(handle, "id") {
+            let anchor = span_node(handle, id);
+            handle.children.borrow_mut().insert(0, anchor);
+        }
+    } else if is_element(handle, "a") {
+        let description = attribute(handle, "aria-label")
+            .map(|description| Marker::LinkDescription(push_text(texts, description)));
+        let access_key = attribute(handle, "accesskey")
+            .and_then(|key| key.trim().chars().next())
+            .map(Marker::AccessKey);
+        if description.is_some() || access_key.is_some() {
+            let mut children = handle.children.borrow_mut();
+            for marker in access_key.iter().chain(description.iter()) {
+                children.insert(0, marker_node(handle, marker));
+            }
+            children.push(marker_node(handle, &Marker::LinkEnd));
+        }
+    }
+}
+
+fn is_element(handle: &Handle, local_name: &str) -> bool {
+    if let NodeData::Element { name, .. } = &handle.data {
+        &*name.local == local_name
+    } else {
+        false
+    }
+}
+
+fn element_name(handle: &Handle) -> Option<&str> {
+    if let NodeData::Element { name, .. } = &handle.data {
+        Some(&*name.local)
+    } else {
+        None
+    }
+}
+
+fn landmark_kind(element: &str) -> Option<LandmarkKind> {
+    match element {
+        "nav" => Some(LandmarkKind::Navigation),
+        "main" => Some(LandmarkKind::Main),
+        "header" => Some(LandmarkKind::Header),
+        "footer" => Some(LandmarkKind::Footer),
+        "aside" => Some(LandmarkKind::Aside),
+        _ => None,
+    }
+}
+
+fn landmark_element(kind: LandmarkKind) -> &'static str {
+    match kind {
+        LandmarkKind::Navigation => "nav",
+        LandmarkKind::Main => "main",
+        LandmarkKind::Header => "header",
+        LandmarkKind::Footer => "footer",
+        LandmarkKind::Aside => "aside",
+    }
+}
+
+fn heading_level(handle: &Handle) -> Option<u8> {
+    if let NodeData::Element { name, .. } = &handle.data {
+        match &*name.local {
+            "h1" => Some(1),
+            "h2" => Some(2),
+            "h3" => Some(3),
+            "h4" => Some(4),
+            "h5" => Some(5),
+            "h6" => Some(6),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+fn attribute(handle: &Handle, local_name: &str) -> Option<String> {
+    if let NodeData::Element { attrs, .. } = &handle.data {
+        attrs
+            .borrow()
+            .iter()
+            .find(|attr| &*attr.name.local == local_name)
+            .map(|attr| attr.value.to_string())
+    } else {
+        None
+    }
+}
+
+/// Replaces an element that is ignored by `html2text` with a block containing a marker.
+///
+/// The attributes of the original element are kept so that its `id` can still be used as a
+/// fragment.
+fn replace_with_marker(parent: &Handle, handle: &Handle, marker: &Marker) -> Handle {
+    let attrs = if let NodeData::Element { attrs, .. } = &handle.data {
+        attrs.borrow().clone()
+    } else {
+        Vec::new()
+    };
+    let node = Node::new(NodeData::Element {
+        name: html5ever::QualName::new(None, ns!(html), local_name!("div")),
+        attrs: cell::RefCell::new(attrs),
+        template_contents: None,
+        mathml_annotation_xml_integration_point: false,
+    });
+    node.parent.set(Some(rc::Rc::downgrade(parent)));
+    let marker = marker_node(&node, marker);
+    node.children.borrow_mut().push(marker);
+    node
+}
+
+/// Replaces an element that is not known to `html2text` with a block element with the given name
+/// and the same attributes and children.
+fn replace_with_block(parent: &Handle, handle: &Handle, name: html5ever::LocalName) -> Handle {
+    let attrs = if let NodeData::Element { attrs, .. } = &handle.data {
+        attrs.borrow().clone()
+    } else {
+        Vec::new()
+    };
+    let node = Node::new(NodeData::Element {
+        name: html5ever::QualName::new(None, ns!(html), name),
+        attrs: cell::RefCell::new(attrs),
+        template_contents: None,
+        mathml_annotation_xml_integration_point: false,
+    });
+    node.parent.set(Some(rc::Rc::downgrade(parent)));
+    let children = std::mem::take(&mut *handle.children.borrow_mut());
+    for child in &children {
+        child.parent.set(Some(rc::Rc::downgrade(&node)));
+    }
+    *node.children.borrow_mut() = children;
+    node
+}
+
+fn has_literals(handle: &Handle) -> bool {
+    if let NodeData::Text { contents } = &handle.data {
+        contents.borrow().contains(NO_BREAK_SPACE)
+    } else {
+        false
+    }
+}
+
+/// Splits the given text node into text nodes and [`Marker::Literal`][] markers so that every
+/// [`NO_BREAK_SPACE`][] character in the text is preceded by a marker and is not mistaken for a
+/// non-breaking space.
+fn mark_literals(parent: &Handle, handle: &Handle) -> Vec<Handle> {
+    let text = match &handle.data {
+        NodeData::Text { contents } => contents.borrow().to_string(),
+        _ => return vec![handle.clone()],
+    };
+    let mut nodes = Vec::new();
+    for (idx, part) in text.split(NO_BREAK_SPACE).enumerate() {
+        let part = if idx > 0 {
+            nodes.push(marker_node(parent, &Marker::Literal));
+            format!("{}{}", NO_BREAK_SPACE, part)
+        } else {
+            part.to_owned()
+        };
+        if !part.is_empty() {
+            nodes.push(text_node(parent, part));
+        }
+    }
+    nodes
+}
+
+fn wrap_children(handle: &Handle, start: Marker, end: Marker) {
+    let start = marker_node(handle, &start);
+    let end = marker_node(handle, &end);
+    let mut children = handle.children.borrow_mut();
+    children.insert(0, start);
+    children.push(end);
+}
+
+/// Inserts the start marker before the first and the end marker after the last non-blank text
+/// node of the given element.
+///
+/// If we inserted the markers as the first and last children of a block element, `html2text`
+/// would attach them to the surrounding lines or render them in separate lines.  Elements
+/// without text don't get markers.
+fn insert_around_text(handle: &Handle, start: Marker, end: Marker) {
+    let mut texts = Vec::new();
+    find_texts(handle, &mut texts);
+    if let (Some(first), Some(last)) = (texts.first(), texts.last()) {
+        insert_sibling(first, &start, false);
+        insert_sibling(last, &end, true);
+    }
+}
+
+/// Collects the non-blank text nodes of the given node that are rendered by `html2text`.
+fn find_texts(handle: &Handle, texts: &mut Vec<Handle>) {
+    for child in handle.children.borrow().iter() {
+        match &child.data {
+            NodeData::Text { contents } if !contents.borrow().trim().is_empty() => {
+                texts.push(child.clone())
+            }
+            NodeData::Element { name, .. } if !matches!(&*name.local, "script" | "style") => {
+                find_texts(child, texts)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Inserts a marker before or after the given node.
+///
+/// Nested landmarks are processed first, so their markers are skipped to keep the markers of the
+/// outer landmark outside of them.
+fn insert_sibling(handle: &Handle, marker: &Marker, after: bool) {
+    let parent = match handle.parent.take() {
+        Some(parent) => parent,
+        None => return,
+    };
+    handle.parent.set(Some(parent.clone()));
+    if let Some(parent) = parent.upgrade() {
+        let node = marker_node(&parent, marker);
+        let mut children = parent.children.borrow_mut();
+        if let Some(mut idx) = children.iter().position(|c| rc::Rc::ptr_eq(c, handle)) {
+            if after {
+                idx += 1;
+                while idx < children.len() && marker_of(&children[idx]) == Some(Marker::LandmarkEnd)
+                {
+                    idx += 1;
+                }
+            } else {
+                while idx > 0 {
+                    match marker_of(&children[idx - 1]) {
+                        Some(Marker::LandmarkStart(_)) => idx -= 1,
+                        _ => break,
+                    }
+                }
+            }
+            children.insert(idx, node);
+        }
+    }
+}
+
+/// Adds the given attribute text to the list of texts and returns its index.
+fn push_text(texts: &mut Vec<String>, text: String) -> usize {
+    texts.push(text);
+    texts.len() - 1
+}
+
+fn marker_of(handle: &Handle) -> Option<Marker> {
+    attribute(handle, "id").and_then(|id| Marker::parse(&id))
+}
+
+fn marker_node(parent: &Handle, marker: &Marker) -> Handle {
+    span_node(parent, marker.id())
+}
+
+fn text_node(parent: &Handle, text: String) -> Handle {
+    let node = Node::new(NodeData::Text {
+        contents: cell::RefCell::new(text.into()),
+    });
+    node.parent.set(Some(rc::Rc::downgrade(parent)));
+    node
+}
+
+fn span_node(parent: &Handle, id: String) -> Handle {
+    let id = html5ever::Attribute {
+        name: html5ever::QualName::new(None, ns!(), local_name!("id")),
+        value: id.into(),
+    };
+    let node = Node::new(NodeData::Element {
+        name: html5ever::QualName::new(None, ns!(html), local_name!("span")),
+        attrs: cell::RefCell::new(vec![id]),
+        template_contents: None,
+        mathml_annotation_xml_integration_point: false,
+    });
+    node.parent.set(Some(rc::Rc::downgrade(parent)));
+    node
+}
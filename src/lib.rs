@@ -11,7 +11,8 @@
 //! using the [`on_link_select`][] and [`on_link_focus`][] methods.
 //!
 //! Typically, you’ll want to wrap the view in a [`ScrollView`][] and add it to a
-//! [`Cursive`][`cursive::Cursive`] instance.
+//! [`Cursive`][`cursive::Cursive`] instance.  The [`scrollable_html`][] method creates a view
+//! that is already wrapped in a [`ScrollView`][].
 //!
 //! ```
 //! // Create the markup view
@@ -63,6 +64,7 @@
 //! [`TextDecorator`]: https://docs.rs/html2text/latest/html2text/render/text_renderer/trait.TextDecorator.html
 //! [`Converter`]: html/trait.Converter.html
 //! [`MarkupView`]: struct.MarkupView.html
+//! [`scrollable_html`]: struct.MarkupView.html#method.scrollable_html
 //! [`RenderedDocument`]: struct.RenderedDocument.html
 //! [`Renderer`]: trait.Renderer.html
 //! [`html`]: struct.MarkupView.html#method.html
@@ -74,21 +76,55 @@
 
 #![warn(missing_docs, rust_2018_idioms)]
 
+#[cfg(feature = "ansi")]
+pub mod ansi;
 #[cfg(feature = "html")]
 pub mod html;
+pub mod plain;
 
+use std::collections;
+use std::ops;
 use std::rc;
+use std::time;
 
 use cursive_core::theme;
-use unicode_width::UnicodeWidthStr as _;
+use unicode_width::{UnicodeWidthChar as _, UnicodeWidthStr as _};
+
+/// The width that is used for rendering if the document should not be reflowed, see
+/// `MarkupView::set_reflow`.
+///
+/// This is wide enough for most tables and diagrams, but still limits the width of paragraphs
+/// without line breaks, as they are wrapped at this width.
+const FIXED_WIDTH: usize = 1000;
+
+/// The time after which the prefix of the quick find is reset.
+const QUICK_FIND_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+/// The number of blank columns between the columns of a multi-column layout.
+const COLUMN_GAP: usize = 2;
+
+/// The maximum interval between the two clicks of a double click.
+const DOUBLE_CLICK_TIMEOUT: time::Duration = time::Duration::from_millis(500);
+
+/// The text of the toggle of an open collapsible section.
+const SECTION_OPEN_MARKER: &str = "▾ ";
+
+/// The text of the toggle of a closed collapsible section.
+const SECTION_CLOSED_MARKER: &str = "▸ ";
 
 /// A view for hypertext that has been rendered by a [`Renderer`][].
 ///
 /// This view displays hypertext (a combination of formatted text and links) that typically has
 /// been parsed from a markup language.  You can use the arrow keys to navigate between the links,
-/// and the Enter key to select a link.  If the focused link is changed, the [`on_link_focus`][]
-/// callback is triggered.  If the focused link is selected using the Enter key, the
-/// [`on_link_select`][] callback is triggered.
+/// the Tab and Shift+Tab keys to cycle through the links, the Home and End keys to jump to the
+/// first and last link in the current line, and the Enter key to select a link.  Links with an
+/// access key can be selected directly by pressing the access key together with the Alt key.  If
+/// the focused link is changed, the [`on_link_focus`][] callback is triggered.  If the focused
+/// link is selected using the Enter key, the [`on_link_select`][] callback is triggered.
+///
+/// Arrow key events are only consumed if the focus can be moved in the requested direction.  If
+/// there is no further link in that direction, the event is ignored so that a parent view, for
+/// example a [`ScrollView`][], can handle it and scroll the document.
 ///
 /// The displayed hypertext is created by a [`Renderer`][] implementation.  The `MarkupView` calls
 /// the [`render`][] method with the size constraint provided by `cursive` and receives a
@@ -98,18 +134,203 @@ use unicode_width::UnicodeWidthStr as _;
 /// You can also limit the available width by setting a maximum line width with the
 /// [`set_maximum_width`][] method.
 ///
+/// Palette colors are only resolved when the view is drawn.  Styles that refer to the palette,
+/// for example the highlight color of the focused link, follow changes of the theme without
+/// rendering the document again.
+///
+/// # Example
+///
+/// ```
+/// use cursive_core::direction::Direction;
+/// use cursive_core::event::{Event, Key};
+/// use cursive_core::View as _;
+///
+/// let html = r#"<p><a href="a">a</a> <a href="b">b</a></p>"#;
+/// let mut view = cursive_markup::MarkupView::html(html);
+/// view.layout((80, 10).into());
+/// assert!(view.take_focus(Direction::none()));
+///
+/// assert!(view.on_event(Event::Key(Key::Right)).is_consumed());
+/// assert!(!view.on_event(Event::Key(Key::Right)).is_consumed());
+/// assert!(!view.on_event(Event::Key(Key::Down)).is_consumed());
+/// assert!(view.on_event(Event::Key(Key::Left)).is_consumed());
+/// assert!(!view.on_event(Event::Key(Key::Up)).is_consumed());
+/// ```
+///
 /// [`RenderedDocument`]: struct.RenderedDocument.html
 /// [`Renderer`]: trait.Renderer.html
 /// [`render`]: trait.Renderer.html#method.render
 /// [`on_link_select`]: #method.on_link_select
 /// [`on_link_focus`]: #method.on_link_focus
 /// [`set_maximum_width`]: #method.set_maximum_width
+/// [`ScrollView`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html
 pub struct MarkupView<R: Renderer + 'static> {
     renderer: R,
+    appended: Vec<Box<dyn Renderer>>,
     doc: Option<RenderedDocument>,
     on_link_focus: Option<rc::Rc<LinkCallback>>,
     on_link_select: Option<rc::Rc<LinkCallback>>,
+    on_link_description: Option<rc::Rc<LinkCallback>>,
+    on_link_activate: Option<rc::Rc<LinkCallback>>,
+    on_link_select_in_new: Option<rc::Rc<LinkCallback>>,
+    activate_key: Option<cursive_core::event::Event>,
     maximum_width: Option<usize>,
+    interactive: bool,
+    link_target_transform: Option<Box<LinkTargetTransform>>,
+    on_near_bottom: Option<cursive_core::event::Callback>,
+    near_bottom_threshold: usize,
+    navigation_order: NavigationOrder,
+    search_focuses_links: bool,
+    last_match: Option<cursive_core::XY<usize>>,
+    effect_fallbacks: Vec<(theme::Effect, theme::Style)>,
+    remember_focus: bool,
+    has_focused: bool,
+    normalize_link_targets: bool,
+    hint: Option<String>,
+    padding: cursive_core::XY<usize>,
+    export_doc: Option<RenderedDocument>,
+    wrap_focus: bool,
+    notify_take_focus: bool,
+    /// The callbacks for changes that have not been caused by an event, triggered with the next
+    /// consumed event.
+    pending_callbacks: Vec<cursive_core::event::Callback>,
+    link_markers: Option<(String, String)>,
+    preserved_focus: Option<usize>,
+    skip_duplicate_links: bool,
+    scheme_styles: collections::HashMap<String, theme::Style>,
+    fragment_line: Option<usize>,
+    open_sections: collections::HashMap<usize, bool>,
+    reflow: bool,
+    highlight_same_target: bool,
+    quick_find: bool,
+    plain_access_keys: bool,
+    quick_find_prefix: String,
+    quick_find_time: Option<time::Instant>,
+    report_full_height: bool,
+    empty_text: Option<String>,
+    on_focus_changed: Option<rc::Rc<FocusCallback>>,
+    /// The focused link that has been reported to the `on_focus_changed` callback.
+    reported_focus: Option<usize>,
+    render_stats: Option<RenderStats>,
+    dim_unfocused: bool,
+    mouse_activation: MouseActivation,
+    columns: usize,
+    on_horizontal_overflow: Option<rc::Rc<OverflowCallback>>,
+    /// Whether the document is wider than the view.
+    overflow: bool,
+    /// The overflow state that has been reported to the `on_horizontal_overflow` callback.
+    reported_overflow: bool,
+    /// The link that has been clicked last and the time of the click.
+    last_click: Option<(usize, time::Instant)>,
+    /// The constraint of the last layout, before applying the padding and the maximum width.
+    last_constraint: Option<cursive_core::XY<usize>>,
+    /// The lines of the document that have been visible when the view was drawn last.
+    visible_lines: std::cell::Cell<Option<(usize, usize)>>,
+    #[cfg(feature = "url")]
+    base_url: Option<url::Url>,
+}
+
+/// A view that shows the positions of the links of a scrollable [`MarkupView`][] in a gutter.
+///
+/// The gutter is a column to the right of the wrapped [`ScrollView`][].  Every row of the gutter
+/// corresponds to a part of the document, and the rows that contain a link are marked, so that
+/// the link density can be seen at a glance.  If the document is taller than the view, the rows
+/// that correspond to the visible part of the document are highlighted like a scroll bar.
+///
+/// # Example
+///
+/// ```
+/// let view = cursive_markup::MarkupView::scrollable_html("<a href='https://rust-lang.org'>Rust</a>");
+/// let mut gutter = cursive_markup::LinkGutter::new(view);
+/// gutter.set_marker('*');
+/// ```
+///
+/// [`MarkupView`]: struct.MarkupView.html
+/// [`ScrollView`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html
+pub struct LinkGutter<R: Renderer + 'static> {
+    view: cursive_core::views::ScrollView<MarkupView<R>>,
+    marker: char,
+    style: theme::Style,
+}
+
+/// The order in which the links of a [`MarkupView`][] are traversed with the arrow keys.
+///
+/// [`MarkupView`]: struct.MarkupView.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NavigationOrder {
+    /// Traverse the links in the order in which they were produced by the renderer.
+    DocumentOrder,
+    /// Traverse the links by their position in the rendered document, from top to bottom and
+    /// from left to right.
+    VisualOrder,
+}
+
+/// The kind of a landmark, i. e. a region of a document with a specific purpose.
+///
+/// See [`RenderedDocument::landmarks`][].
+///
+/// [`RenderedDocument::landmarks`]: struct.RenderedDocument.html#method.landmarks
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum LandmarkKind {
+    /// A block of navigation links, for example a `<nav>` element.
+    Navigation,
+    /// The main content of the document, for example a `<main>` element.
+    Main,
+    /// Introductory content, for example a `<header>` element.
+    Header,
+    /// The footer of the document or of a section, for example a `<footer>` element.
+    Footer,
+    /// Content that is only indirectly related to the main content, for example an `<aside>`
+    /// element.
+    Aside,
+}
+
+/// The mouse clicks that select a link in a [`MarkupView`][].
+///
+/// [`MarkupView`]: struct.MarkupView.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MouseActivation {
+    /// A single click focuses and selects a link.
+    SingleClick,
+    /// A single click focuses a link, and a double click selects it.
+    DoubleClick,
+}
+
+/// The display of control characters, for example in the output of a command.
+///
+/// Line breaks and tabs are handled by the renderers and are not affected by this setting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ControlCharDisplay {
+    /// Display control characters in caret notation, for example `^C`.
+    ///
+    /// C1 control characters are prefixed with `M-`, for example `M-^E`.
+    Caret,
+    /// Display control characters as Unicode control pictures, for example `␃`.
+    ///
+    /// C1 control characters don't have a control picture and are displayed as `�`.
+    Pictures,
+    /// Remove control characters.
+    Strip,
+}
+
+/// Statistics about the last rendering of a [`MarkupView`][].
+///
+/// See [`MarkupView::last_render_stats`][].
+///
+/// [`MarkupView`]: struct.MarkupView.html
+/// [`MarkupView::last_render_stats`]: struct.MarkupView.html#method.last_render_stats
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct RenderStats {
+    /// The time that was needed to render the document.
+    pub duration: time::Duration,
+    /// The number of lines of the rendered document.
+    pub lines: usize,
+    /// The number of links of the rendered document, without the toggles of collapsible sections.
+    pub links: usize,
+    /// The width that was passed to the renderer.
+    pub width: usize,
 }
 
 /// A callback that is triggered for a link.
@@ -120,6 +341,28 @@ pub struct MarkupView<R: Renderer + 'static> {
 /// [`Cursive`]: https://docs.rs/cursive/latest/cursive/struct.Cursive.html
 pub type LinkCallback = dyn Fn(&mut cursive_core::Cursive, &str);
 
+/// A callback that is triggered if the focused link is changed.
+///
+/// The first argument is a mutable reference to the current [`Cursive`][] instance.  The second
+/// and the third argument are the indices of the previously and the newly focused link.
+///
+/// [`Cursive`]: https://docs.rs/cursive/latest/cursive/struct.Cursive.html
+pub type FocusCallback = dyn Fn(&mut cursive_core::Cursive, usize, usize);
+
+/// A callback that is triggered if the document starts or stops overflowing the view
+/// horizontally.
+///
+/// The first argument is a mutable reference to the current [`Cursive`][] instance.  The second
+/// argument is whether the document is wider than the view.
+///
+/// [`Cursive`]: https://docs.rs/cursive/latest/cursive/struct.Cursive.html
+pub type OverflowCallback = dyn Fn(&mut cursive_core::Cursive, bool);
+
+/// A function that transforms a link target before it is passed to a [`LinkCallback`][].
+///
+/// [`LinkCallback`]: type.LinkCallback.html
+pub type LinkTargetTransform = dyn Fn(&str) -> String;
+
 /// A renderer that produces a hypertext document.
 pub trait Renderer {
     /// Renders this document within the given size constraint and returns the result.
@@ -128,6 +371,95 @@ pub trait Renderer {
     ///
     /// [`MarkupView`]: struct.MarkupView.html
     fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument;
+
+    /// Returns the minimum width that this renderer uses to render a document.
+    ///
+    /// If the width of the constraint passed to [`render`][] is smaller, the document is
+    /// rendered with this width instead.  [`MarkupView`][] applies this minimum width in
+    /// [`MarkupView::effective_constraint`][] so that it does not render the document again for
+    /// widths below the minimum width.  The default implementation returns zero.
+    ///
+    /// [`render`]: #tymethod.render
+    /// [`MarkupView`]: struct.MarkupView.html
+    /// [`MarkupView::effective_constraint`]: struct.MarkupView.html#method.effective_constraint
+    fn minimum_width(&self) -> usize {
+        0
+    }
+}
+
+impl<R: Renderer + ?Sized> Renderer for Box<R> {
+    fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
+        (**self).render(constraint)
+    }
+
+    fn minimum_width(&self) -> usize {
+        (**self).minimum_width()
+    }
+}
+
+/// A renderer that returns a fixed list of lines, intended for tests.
+///
+/// *Requires the `test-util` feature.*
+///
+/// This renderer ignores the size constraint and always produces the same document with the
+/// given lines.  It can be used to test the behavior of a [`MarkupView`][] (for example the link
+/// navigation) without depending on a markup parser.
+///
+/// [`MarkupView`]: struct.MarkupView.html
+#[cfg(feature = "test-util")]
+#[derive(Clone, Debug, Default)]
+pub struct TestRenderer {
+    lines: Vec<Vec<Element>>,
+}
+
+/// A backend that records the printed text and colors, intended for tests.
+///
+/// *Requires the `test-util` feature.*
+///
+/// This backend does not produce any output.  Instead, it stores the text and the color of every
+/// printed cell so that the output of a view can be checked after drawing it with a printer
+/// created by [`printer`][].
+///
+/// # Example
+///
+/// ```
+/// use cursive_core::theme::{ColorStyle, PaletteColor, Theme};
+/// use cursive_core::View as _;
+///
+/// let html = "<p><a href='https://rust-lang.org'><b>Rust</b> <code>lang</code> org</a></p>";
+/// let mut view = cursive_markup::MarkupView::html(html);
+/// view.layout((20, 5).into());
+///
+/// let theme = Theme::default();
+/// let backend = cursive_markup::TestBackend::new((20, 5).into());
+/// view.draw(&backend.printer(&theme));
+///
+/// // All parts of the focused link are highlighted in the same way.
+/// let highlight = ColorStyle::new(PaletteColor::Highlight, PaletteColor::View)
+///     .resolve(&theme.palette, cursive_core::theme::ColorPair::from_256colors(0, 0));
+/// assert_eq!(backend.row(0), "Rust `lang` org");
+/// for x in 0..15 {
+///     assert_eq!(backend.color((x, 0).into()), Some(highlight));
+/// }
+/// ```
+///
+/// [`printer`]: #method.printer
+#[cfg(feature = "test-util")]
+#[derive(Debug)]
+pub struct TestBackend {
+    size: cursive_core::XY<usize>,
+    cells: std::cell::RefCell<collections::BTreeMap<(usize, usize), (char, theme::ColorPair)>>,
+    color: std::cell::Cell<theme::ColorPair>,
+}
+
+/// An error that occurred while preparing a document for rendering.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RenderError {
+    /// The document could not be decoded with the given character encoding.
+    Decode(String),
+    /// The given character encoding is not supported.
+    UnknownCharset(String),
 }
 
 /// A rendered hypertext document that consists of lines of formatted text and links.
@@ -135,6 +467,14 @@ pub trait Renderer {
 pub struct RenderedDocument {
     lines: Vec<Vec<RenderedElement>>,
     link_handler: LinkHandler,
+    anchors: Vec<(String, usize)>,
+    headings: Vec<(u8, String, usize)>,
+    /// The body lines of the collapsible sections and whether they are open per default.
+    sections: Vec<(ops::Range<usize>, bool)>,
+    landmarks: Vec<(LandmarkKind, ops::Range<usize>)>,
+    line_styles: collections::HashMap<usize, theme::Style>,
+    source_lines: collections::HashMap<usize, usize>,
+    max_links: Option<usize>,
     size: cursive_core::XY<usize>,
     constraint: cursive_core::XY<usize>,
 }
@@ -145,19 +485,52 @@ pub struct Element {
     text: String,
     style: theme::Style,
     link_target: Option<String>,
+    link_disabled: bool,
+    link_description: Option<String>,
+    access_key: Option<char>,
+    section_toggle: Option<usize>,
+    embed_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Default)]
 struct RenderedElement {
     text: String,
+    /// The width of the text, cached to avoid recomputing it every time the element is drawn.
+    width: usize,
     style: theme::Style,
     link_idx: Option<usize>,
+    embed_id: Option<String>,
 }
 
 #[derive(Clone, Debug, Default)]
 struct LinkHandler {
     links: Vec<Link>,
     focus: usize,
+    /// The indices of the links in navigation order.
+    order: Vec<usize>,
+    skip_duplicates: bool,
+    /// The number of links without the continuation parts of links that span multiple rows.
+    link_count: usize,
+}
+
+/// Wraps a stream of elements into lines, see `RenderedDocument::push_wrapped`.
+///
+/// The elements are stored together with the index of the element they have been taken from so
+/// that consecutive parts of the same element can be merged again.
+#[derive(Debug)]
+struct LineWrapper {
+    width: usize,
+    /// The width of the first line.
+    first_width: usize,
+    lines: Vec<Vec<Element>>,
+    line: Vec<(usize, Element)>,
+    line_width: usize,
+    /// The whitespace before the current word.
+    space: Vec<(usize, Element)>,
+    space_width: usize,
+    /// The parts of the current word.
+    word: Vec<(usize, Element)>,
+    word_width: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -165,6 +538,29 @@ struct Link {
     position: cursive_core::XY<usize>,
     width: usize,
     target: String,
+    description: Option<String>,
+    access_key: Option<char>,
+    /// The index of the collapsible section that is toggled by this link, if any.
+    section: Option<usize>,
+}
+
+impl MarkupView<Box<dyn Renderer>> {
+    /// Creates a new `MarkupView` that uses the given boxed renderer.
+    ///
+    /// This can be used to select the renderer at runtime, for example based on the content type
+    /// of a document, see [`renderer_for`][].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let renderer = cursive_markup::renderer_for("text/html", "<p>Hello</p>").unwrap();
+    /// let view = cursive_markup::MarkupView::boxed(renderer);
+    /// ```
+    ///
+    /// [`renderer_for`]: fn.renderer_for.html
+    pub fn boxed(renderer: Box<dyn Renderer>) -> MarkupView<Box<dyn Renderer>> {
+        MarkupView::with_renderer(renderer)
+    }
 }
 
 #[cfg(feature = "html")]
@@ -175,6 +571,43 @@ impl MarkupView<html::RichRenderer> {
     pub fn html(html: &str) -> MarkupView<html::RichRenderer> {
         MarkupView::with_renderer(html::Renderer::new(html))
     }
+
+    /// Creates a new `MarkupView` that uses a rich text HTML renderer, wrapped in a
+    /// [`ScrollView`][].
+    ///
+    /// The scroll view only scrolls vertically and keeps the focused link visible.  The markup
+    /// view can be accessed with the [`get_inner`][] and [`get_inner_mut`][] methods of the
+    /// scroll view.
+    ///
+    /// *Requires the `html` feature (enabled per default).*
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut view = cursive_markup::MarkupView::scrollable_html("<p>Hello world</p>");
+    /// view.get_inner_mut().set_maximum_width(80);
+    /// ```
+    ///
+    /// [`ScrollView`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html
+    /// [`get_inner`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html#method.get_inner
+    /// [`get_inner_mut`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html#method.get_inner_mut
+    pub fn scrollable_html(
+        html: &str,
+    ) -> cursive_core::views::ScrollView<MarkupView<html::RichRenderer>> {
+        cursive_core::views::ScrollView::new(MarkupView::html(html))
+            .scroll_x(false)
+            .scroll_y(true)
+    }
+}
+
+#[cfg(feature = "ansi")]
+impl MarkupView<ansi::Renderer> {
+    /// Creates a new `MarkupView` that renders text with ANSI escape sequences.
+    ///
+    /// *Requires the `ansi` feature.*
+    pub fn ansi(text: &str) -> MarkupView<ansi::Renderer> {
+        MarkupView::with_renderer(ansi::Renderer::new(text))
+    }
 }
 
 impl<R: Renderer + 'static> MarkupView<R> {
@@ -182,18 +615,128 @@ impl<R: Renderer + 'static> MarkupView<R> {
     pub fn with_renderer(renderer: R) -> MarkupView<R> {
         MarkupView {
             renderer,
+            appended: Vec::new(),
             doc: None,
             on_link_focus: None,
             on_link_select: None,
+            on_link_description: None,
+            on_link_activate: None,
+            on_link_select_in_new: None,
+            activate_key: None,
             maximum_width: None,
+            interactive: true,
+            link_target_transform: None,
+            on_near_bottom: None,
+            near_bottom_threshold: 0,
+            navigation_order: NavigationOrder::DocumentOrder,
+            search_focuses_links: false,
+            last_match: None,
+            effect_fallbacks: Vec::new(),
+            remember_focus: true,
+            has_focused: false,
+            normalize_link_targets: false,
+            hint: None,
+            padding: (0, 0).into(),
+            export_doc: None,
+            wrap_focus: false,
+            notify_take_focus: false,
+            pending_callbacks: Vec::new(),
+            link_markers: None,
+            preserved_focus: None,
+            skip_duplicate_links: false,
+            scheme_styles: collections::HashMap::new(),
+            fragment_line: None,
+            open_sections: collections::HashMap::new(),
+            reflow: true,
+            highlight_same_target: false,
+            quick_find: false,
+            plain_access_keys: false,
+            quick_find_prefix: String::new(),
+            quick_find_time: None,
+            report_full_height: true,
+            empty_text: None,
+            on_focus_changed: None,
+            reported_focus: None,
+            render_stats: None,
+            mouse_activation: MouseActivation::DoubleClick,
+            columns: 1,
+            on_horizontal_overflow: None,
+            overflow: false,
+            reported_overflow: false,
+            last_click: None,
+            last_constraint: None,
+            visible_lines: std::cell::Cell::new(None),
+            dim_unfocused: false,
+            #[cfg(feature = "url")]
+            base_url: None,
+        }
+    }
+
+    /// Replaces the content of this view with the document produced by the given renderer.
+    ///
+    /// The appended documents (see [`append`][]) are removed and the document is rendered again
+    /// during the next layout.  The first link of the new document is focused, and the
+    /// collapsible sections are displayed in their default state.  See
+    /// [`set_content_preserving_focus`][] for keeping the focused link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let html = "<details><summary>More</summary><p>Body</p></details>";
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// view.layout((80, 10).into());
+    /// assert!(view.on_event(Event::Key(Key::Enter)).is_consumed());
+    /// view.layout((80, 10).into());
+    /// assert!(view.document().unwrap().to_plain_text().contains("Body"));
+    ///
+    /// view.set_content(cursive_markup::html::Renderer::new(html));
+    /// view.layout((80, 10).into());
+    /// assert!(!view.document().unwrap().to_plain_text().contains("Body"));
+    /// ```
+    ///
+    /// [`append`]: #method.append
+    /// [`set_content_preserving_focus`]: #method.set_content_preserving_focus
+    pub fn set_content(&mut self, renderer: R) {
+        self.replace_content(renderer, false);
+    }
+
+    /// Replaces the content of this view and keeps the index of the focused link.
+    ///
+    /// This is useful for reloading a document that has only changed slightly:  If the new
+    /// document has fewer links, the last link is focused.  As the view scrolls to the focused
+    /// link, this also keeps the scroll position close to the previous position.  Like with
+    /// [`set_content`][], the collapsible sections are displayed in their default state.
+    ///
+    /// [`set_content`]: #method.set_content
+    pub fn set_content_preserving_focus(&mut self, renderer: R) {
+        self.replace_content(renderer, true);
+    }
+
+    /// Appends the document produced by the given renderer to this view.
+    ///
+    /// The appended document is rendered with the same constraint as the current document and
+    /// added after its last line without re-rendering the current document.  If the available
+    /// width changes, the appended renderers are invoked again together with the main renderer.
+    pub fn append<A: Renderer + 'static>(&mut self, renderer: A) {
+        if let Some(doc) = &mut self.doc {
+            doc.extend(renderer.render(doc.constraint));
+            doc.link_handler.set_order(self.navigation_order);
         }
+        self.appended.push(Box::new(renderer));
+        self.export_doc = None;
     }
 
     /// Sets the callback that is triggered if the link focus is changed.
     ///
-    /// Note that this callback is only triggered if the link focus is changed with the arrow keys.
-    /// It is not triggered if the view takes focus.  The callback will receive the target of the
-    /// link as an argument.
+    /// Note that this callback is only triggered if the link focus is changed with the keyboard.
+    /// Per default, it is not triggered if the view takes focus, see
+    /// [`set_notify_take_focus`][].  The callback will receive the target of the link as an
+    /// argument.
+    ///
+    /// [`set_notify_take_focus`]: #method.set_notify_take_focus
     pub fn on_link_focus<F: Fn(&mut cursive_core::Cursive, &str) + 'static>(&mut self, f: F) {
         self.on_link_focus = Some(rc::Rc::new(f));
     }
@@ -206,163 +749,3191 @@ impl<R: Renderer + 'static> MarkupView<R> {
         self.on_link_select = Some(rc::Rc::new(f));
     }
 
-    /// Sets the maximum width of the view.
+    /// Sets the callback that is triggered if a link is selected to be opened in a new view.
     ///
-    /// This means that the width that is available for the renderer is limited to the given value.
-    pub fn set_maximum_width(&mut self, width: usize) {
-        self.maximum_width = Some(width);
+    /// This callback is triggered if a link is focused and the user presses Ctrl+Enter, for
+    /// example to open the link in a new layer instead of replacing the current content.  Note
+    /// that not all terminals can distinguish Ctrl+Enter from Enter.  The callback will receive
+    /// the same target as [`on_link_select`][].
+    ///
+    /// [`on_link_select`]: #method.on_link_select
+    pub fn on_link_select_in_new<F: Fn(&mut cursive_core::Cursive, &str) + 'static>(
+        &mut self,
+        f: F,
+    ) {
+        self.on_link_select_in_new = Some(rc::Rc::new(f));
     }
 
-    fn render(&mut self, mut constraint: cursive_core::XY<usize>) -> cursive_core::XY<usize> {
-        let mut last_focus = 0;
-
-        if let Some(width) = self.maximum_width {
-            constraint.x = std::cmp::min(width, constraint.x);
-        }
+    /// Sets the callback that is triggered if a link is activated.
+    ///
+    /// This callback is triggered if a link is focused and the user presses the key set with
+    /// [`set_activate_key`][].  In contrast to [`on_link_select`][], it is intended for actions
+    /// that keep the current view, for example copying or previewing the link target.  The
+    /// callback will receive the target of the link as an argument.
+    ///
+    /// [`set_activate_key`]: #method.set_activate_key
+    /// [`on_link_select`]: #method.on_link_select
+    pub fn on_link_activate<F: Fn(&mut cursive_core::Cursive, &str) + 'static>(&mut self, f: F) {
+        self.on_link_activate = Some(rc::Rc::new(f));
+    }
 
-        if let Some(doc) = &self.doc {
-            if constraint.x == doc.constraint.x {
-                return doc.size;
-            }
-            last_focus = doc.link_handler.focus;
-        }
+    /// Sets the event that activates the focused link, see [`on_link_activate`][].
+    ///
+    /// Per default, no event is set and links cannot be activated.
+    ///
+    /// [`on_link_activate`]: #method.on_link_activate
+    pub fn set_activate_key(&mut self, event: Option<cursive_core::event::Event>) {
+        self.activate_key = event;
+    }
 
-        let mut doc = self.renderer.render(constraint);
+    /// Sets the callback that is triggered with the old and the new index of the focused link if
+    /// the focus is changed.
+    ///
+    /// In contrast to [`on_link_focus`][], this callback is also triggered for focus changes that
+    /// are not caused by a key press, for example by [`take_focus`][], [`search_next`][] or
+    /// [`set_content_preserving_focus`][].  As the callback needs access to the `Cursive`
+    /// instance, these changes are queued when they happen and triggered when the view consumes
+    /// the next event, before the callbacks for this event.  The indices are the indices of the
+    /// links in document order.  Replacing the content with [`set_content`][] is not reported as a
+    /// focus change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, EventResult, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let changes = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    /// let html = r#"<p><a href="a">a</a> <a href="b">b</a> <a href="c">c</a></p>"#;
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// let log = changes.clone();
+    /// view.on_focus_changed(move |_, old, new| log.borrow_mut().push((old, new)));
+    /// view.set_search_focuses_links(true);
+    /// view.layout((80, 10).into());
+    ///
+    /// view.search_next("c");
+    /// let mut siv = cursive_core::Cursive::new();
+    /// match view.on_event(Event::Key(Key::Left)) {
+    ///     EventResult::Consumed(Some(callback)) => callback(&mut siv),
+    ///     _ => panic!("missing callback"),
+    /// }
+    /// assert_eq!(*changes.borrow(), vec![(0, 2), (2, 1)]);
+    /// ```
+    ///
+    /// [`on_link_focus`]: #method.on_link_focus
+    /// [`take_focus`]: #method.take_focus
+    /// [`search_next`]: #method.search_next
+    /// [`set_content_preserving_focus`]: #method.set_content_preserving_focus
+    /// [`set_content`]: #method.set_content
+    pub fn on_focus_changed<F: Fn(&mut cursive_core::Cursive, usize, usize) + 'static>(
+        &mut self,
+        f: F,
+    ) {
+        self.on_focus_changed = Some(rc::Rc::new(f));
+    }
 
-        // TODO: Rendering the document with a different width may lead to links being split up (or
-        // previously split up links being no longer split up).  Ideally, we would adjust the focus
-        // for these changes.
-        if last_focus < doc.link_handler.links.len() {
-            doc.link_handler.focus = last_focus;
-        }
-        let size = doc.size;
-        self.doc = Some(doc);
-        size
+    /// Sets the callback that is triggered if the document starts or stops overflowing the view
+    /// horizontally.
+    ///
+    /// The callback receives `true` if the rendered document is wider than the view, for example
+    /// because of a wide table or because reflowing is disabled with [`set_reflow`][], and
+    /// `false` if it fits into the view again.  It is only triggered if this state changes.  As
+    /// the callback needs access to the `Cursive` instance, the changes are queued during the
+    /// layout and triggered when the view consumes the next event, before the callbacks for this
+    /// event.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, EventResult, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let states = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    /// let html = r#"<p>A long line of text</p><p><a href="a">a</a> <a href="b">b</a></p>"#;
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// view.set_reflow(false);
+    /// let log = states.clone();
+    /// view.on_horizontal_overflow(move |_, overflow| log.borrow_mut().push(overflow));
+    /// view.layout((10, 10).into());
+    /// view.layout((80, 10).into());
+    /// view.layout((10, 10).into());
+    ///
+    /// let mut siv = cursive_core::Cursive::new();
+    /// match view.on_event(Event::Key(Key::Right)) {
+    ///     EventResult::Consumed(Some(callback)) => callback(&mut siv),
+    ///     _ => panic!("missing callback"),
+    /// }
+    /// assert_eq!(*states.borrow(), vec![true, false, true]);
+    /// ```
+    ///
+    /// [`set_reflow`]: #method.set_reflow
+    pub fn on_horizontal_overflow<F: Fn(&mut cursive_core::Cursive, bool) + 'static>(
+        &mut self,
+        f: F,
+    ) {
+        self.on_horizontal_overflow = Some(rc::Rc::new(f));
     }
-}
 
-impl<R: Renderer + 'static> cursive_core::View for MarkupView<R> {
-    fn draw(&self, printer: &cursive_core::Printer<'_, '_>) {
-        let doc = &self.doc.as_ref().expect("layout not called before draw");
-        for (y, line) in doc.lines.iter().enumerate() {
-            let mut x = 0;
-            for element in line {
-                let mut style = element.style;
-                if let Some(link_idx) = element.link_idx {
-                    if printer.focused && doc.link_handler.focus == link_idx {
-                        style = style.combine(theme::PaletteColor::Highlight);
-                    }
-                }
-                printer.with_style(style, |printer| printer.print((x, y), &element.text));
-                x += element.text.width();
-            }
-        }
+    /// Sets the callback that is triggered with the description of a link if the link focus is
+    /// changed.
+    ///
+    /// This callback is triggered together with the [`on_link_focus`][] callback.  Instead of the
+    /// link target, it receives the description of the link (for example the `aria-label`
+    /// attribute of an HTML link) or, if the link does not have a description, the text of the
+    /// link.  It can be used to display the focused link in a status bar.
+    ///
+    /// [`on_link_focus`]: #method.on_link_focus
+    pub fn on_link_description<F: Fn(&mut cursive_core::Cursive, &str) + 'static>(&mut self, f: F) {
+        self.on_link_description = Some(rc::Rc::new(f));
     }
 
-    fn layout(&mut self, constraint: cursive_core::XY<usize>) {
-        self.render(constraint);
+    /// Sets the callback that is triggered if a link close to the end of the document is focused.
+    ///
+    /// This callback is triggered every time the link focus is changed with the arrow keys and the
+    /// focused link is at most `threshold` lines away from the last line of the document.  It can
+    /// be used to load more content when the user reaches the end of the document.
+    pub fn on_near_bottom<F: Fn(&mut cursive_core::Cursive) + 'static>(
+        &mut self,
+        threshold: usize,
+        f: F,
+    ) {
+        self.near_bottom_threshold = threshold;
+        self.on_near_bottom = Some(cursive_core::event::Callback::from_fn(f));
     }
 
-    fn required_size(&mut self, constraint: cursive_core::XY<usize>) -> cursive_core::XY<usize> {
-        self.render(constraint)
+    /// Sets a function that transforms link targets before they are passed to the callbacks.
+    ///
+    /// The transformation is applied to the link target before the [`on_link_focus`][] and
+    /// [`on_link_select`][] callbacks are called, for example to resolve relative URLs or to decode
+    /// percent-encoded characters.  Per default, the link targets are passed unchanged.
+    ///
+    /// [`on_link_focus`]: #method.on_link_focus
+    /// [`on_link_select`]: #method.on_link_select
+    pub fn set_link_target_transform<F: Fn(&str) -> String + 'static>(&mut self, f: F) {
+        self.link_target_transform = Some(Box::new(f));
     }
 
-    fn take_focus(&mut self, direction: cursive_core::direction::Direction) -> bool {
-        self.doc
-            .as_mut()
-            .map(|doc| doc.link_handler.take_focus(direction))
-            .unwrap_or_default()
+    /// Sets whether link targets should be normalized before they are passed to the callbacks.
+    ///
+    /// If this option is enabled, the link targets are converted into the Unicode normalization
+    /// form C (see [`normalize_link_target`][]) before the link target transformation is applied,
+    /// so that equal targets in different normalization forms can be compared.  Per default, the
+    /// link targets are passed unchanged.
+    ///
+    /// [`normalize_link_target`]: fn.normalize_link_target.html
+    pub fn set_normalize_link_targets(&mut self, normalize: bool) {
+        self.normalize_link_targets = normalize;
     }
 
-    fn on_event(&mut self, event: cursive_core::event::Event) -> cursive_core::event::EventResult {
-        use cursive_core::direction::Absolute;
-        use cursive_core::event::{Callback, Event, EventResult, Key};
+    /// Sets a hint, for example a short keyboard help, that is displayed in the last row of the
+    /// view.
+    ///
+    /// The view reserves an additional row below the document for the hint, so the hint does not
+    /// cover any content.  Per default, no hint is displayed.
+    pub fn set_hint(&mut self, hint: Option<String>) {
+        self.hint = hint;
+    }
 
-        let link_handler = if let Some(doc) = self.doc.as_mut() {
-            if doc.link_handler.links.is_empty() {
-                return EventResult::Ignored;
-            } else {
-                &mut doc.link_handler
-            }
-        } else {
-            return EventResult::Ignored;
-        };
+    /// Sets the padding between the left and top edges of the view and the document.
+    ///
+    /// The width that is available for the renderer is reduced by the left padding.  Note that
+    /// the positions in the [`RenderedDocument`][] do not include the padding.  Per default, there
+    /// is no padding.
+    ///
+    /// [`RenderedDocument`]: struct.RenderedDocument.html
+    pub fn set_padding(&mut self, left: usize, top: usize) {
+        self.padding = (left, top).into();
+    }
 
-        // TODO: implement mouse support
+    /// Sets whether the [`on_link_focus`][] callback should be triggered if the view takes focus.
+    ///
+    /// As `cursive` does not allow views to trigger callbacks when they take focus, the callback
+    /// is queued and triggered together with the next event that is consumed by the view, before
+    /// the callbacks for this event.  Per default, this option is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::direction::Direction;
+    /// use cursive_core::event::{Event, EventResult, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let focused = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    /// let html = r#"<p><a href="a">a</a> <a href="b">b</a></p>"#;
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// let targets = focused.clone();
+    /// view.on_link_focus(move |_, target| targets.borrow_mut().push(target.to_owned()));
+    /// view.set_notify_take_focus(true);
+    /// view.layout((80, 10).into());
+    /// assert!(view.take_focus(Direction::none()));
+    ///
+    /// // The notification is not lost if the event moves the focus.
+    /// let mut siv = cursive_core::Cursive::new();
+    /// match view.on_event(Event::Key(Key::Right)) {
+    ///     EventResult::Consumed(Some(callback)) => callback(&mut siv),
+    ///     _ => panic!("missing callback"),
+    /// }
+    /// assert_eq!(*focused.borrow(), vec!["a", "b"]);
+    /// ```
+    ///
+    /// [`on_link_focus`]: #method.on_link_focus
+    pub fn set_notify_take_focus(&mut self, notify: bool) {
+        self.notify_take_focus = notify;
+    }
 
-        let focus_changed = match event {
-            Event::Key(Key::Left) => link_handler.move_focus(Absolute::Left),
-            Event::Key(Key::Right) => link_handler.move_focus(Absolute::Right),
-            Event::Key(Key::Up) => link_handler.move_focus(Absolute::Up),
-            Event::Key(Key::Down) => link_handler.move_focus(Absolute::Down),
-            _ => false,
-        };
+    /// Sets whether the Tab key should wrap around at the last link.
+    ///
+    /// If this option is enabled, pressing Tab at the last link focuses the first link, and
+    /// pressing Shift+Tab at the first link focuses the last link.  Otherwise, the event is
+    /// ignored so that the focus can leave the view.  Per default, this option is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, EventResult, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let mut view = cursive_markup::MarkupView::html("<a href='a'>a</a> <a href='b'>b</a>");
+    /// view.layout((20, 1).into());
+    /// view.take_focus(cursive_core::direction::Direction::none());
+    ///
+    /// // Tab moves the focus to the second link, and is ignored at the last link.
+    /// assert!(view.on_event(Event::Key(Key::Tab)).is_consumed());
+    /// assert!(matches!(view.on_event(Event::Key(Key::Tab)), EventResult::Ignored));
+    /// assert_eq!(view.focused_link_text().as_deref(), Some("b"));
+    ///
+    /// view.set_wrap_focus(true);
+    /// assert!(view.on_event(Event::Key(Key::Tab)).is_consumed());
+    /// assert_eq!(view.focused_link_text().as_deref(), Some("a"));
+    /// ```
+    pub fn set_wrap_focus(&mut self, wrap: bool) {
+        self.wrap_focus = wrap;
+    }
 
-        if focus_changed {
-            let target = link_handler.links[link_handler.focus].target.clone();
-            EventResult::Consumed(
-                self.on_link_focus
-                    .clone()
-                    .map(|f| Callback::from_fn(move |s| f(s, &target))),
-            )
-        } else if event == Event::Key(Key::Enter) {
-            let target = link_handler.links[link_handler.focus].target.clone();
-            EventResult::Consumed(
-                self.on_link_select
-                    .clone()
-                    .map(|f| Callback::from_fn(move |s| f(s, &target))),
-            )
-        } else {
-            EventResult::Ignored
-        }
+    /// Sets the strings that are displayed before and after every link.
+    ///
+    /// The markers make it possible to recognize links on terminals that do not support the
+    /// underline effect, for example `("[".to_owned(), "]".to_owned())`.  They are inserted into
+    /// the rendered document, so they are taken into account for the link positions and the size
+    /// of the document.  Note that the markers are added after the document has been rendered, so
+    /// lines with links can exceed the available width.  Per default, no markers are displayed.
+    pub fn set_link_markers(&mut self, markers: Option<(String, String)>) {
+        self.link_markers = markers;
+        self.invalidate(true);
     }
 
-    fn important_area(&self, _: cursive_core::XY<usize>) -> cursive_core::Rect {
-        if let Some(doc) = &self.doc {
-            doc.link_handler.important_area()
-        } else {
-            cursive_core::Rect::from((0, 0))
+    /// Sets whether links with the same target as the focused link should be skipped when moving
+    /// the focus.
+    ///
+    /// If this option is enabled, the arrow keys and the Tab key move the focus to the next link
+    /// with a different target, for example to skip repeated links in navigation bars.  Per
+    /// default, every link is focused.
+    pub fn set_skip_duplicate_links(&mut self, skip: bool) {
+        self.skip_duplicate_links = skip;
+        if let Some(doc) = &mut self.doc {
+            doc.link_handler.skip_duplicates = skip;
         }
     }
-}
 
-impl RenderedDocument {
-    /// Creates a new rendered document with the given size constraint.
+    /// Sets the maximum width of the view.
     ///
-    /// The size constraint is used to check whether a cached document can be reused or whether it
-    /// has to be rendered for the new constraint.  It is *not* enforced by this struct!
-    pub fn new(constraint: cursive_core::XY<usize>) -> RenderedDocument {
-        RenderedDocument {
-            lines: Vec::new(),
-            link_handler: Default::default(),
-            size: (0, 0).into(),
-            constraint,
-        }
+    /// This means that the width that is available for the renderer is limited to the given value.
+    pub fn set_maximum_width(&mut self, width: usize) {
+        self.maximum_width = Some(width);
     }
 
-    /// Appends a rendered line to the document.
-    pub fn push_line<I: IntoIterator<Item = Element>>(&mut self, line: I) {
-        let mut rendered_line = Vec::new();
-        let y = self.lines.len();
-        let mut x = 0;
+    /// Sets whether the document should be reflowed to fit the width of the view.
+    ///
+    /// If this option is disabled, the document is rendered once with a fixed width of
+    /// 1000 columns, regardless of the width of the view and the maximum width, and is not
+    /// rendered again if the width of the view changes.  This keeps content like ASCII diagrams
+    /// and wide tables intact.  To be able to view the complete document, wrap the view in a
+    /// [`ScrollView`][] that scrolls horizontally.  Paragraphs are still wrapped at 1000 columns.
+    /// Changing this option renders the document again at the next layout.  Per default, the
+    /// document is reflowed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    ///
+    /// let mut view = cursive_markup::MarkupView::html("<p>aaaa bbbb cccc</p>");
+    /// view.layout((9, 10).into());
+    /// assert_eq!(view.content_size(), Some((9, 2).into()));
+    ///
+    /// view.set_reflow(false);
+    /// assert!(view.needs_render());
+    /// view.layout((9, 10).into());
+    /// assert_eq!(view.content_size(), Some((14, 1).into()));
+    /// ```
+    ///
+    /// [`ScrollView`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html
+    pub fn set_reflow(&mut self, reflow: bool) {
+        if reflow != self.reflow {
+            self.reflow = reflow;
+            self.invalidate(true);
+        }
+    }
+
+    /// Sets the number of columns that the document is arranged in.
+    ///
+    /// If more than one column is set, the document is rendered with the width of one column and
+    /// the lines are distributed to the given number of columns with the same height, like in a
+    /// newspaper.  The columns are separated by two blank columns.  The links are numbered
+    /// column by column, so the focus moves to the next column after the last link of a column.
+    /// Line styles set with [`RenderedDocument::set_line_style`][] are not supported in a
+    /// multi-column layout.  Per default, the document is displayed in one column.
+    ///
+    /// Positions in the [`document`][] refer to the arranged columns.  Line ranges that continue
+    /// in the next column, for example the lines of a landmark, cover all rows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    ///
+    /// let html = "<a href='a'>a</a><br><a href='b'>b</a><br><nav><a href='c'>c</a><br>\
+    ///             <a href='d'>d</a></nav>";
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// view.set_columns(2);
+    /// view.layout((20, 2).into());
+    ///
+    /// let doc = view.document().unwrap();
+    /// assert_eq!(doc.to_plain_text(), "a          c\nb          d\n");
+    /// assert_eq!(doc.link_at((0, 1).into()), Some(1));
+    /// assert_eq!(doc.link_at((11, 0).into()), Some(2));
+    /// assert_eq!(doc.link_at((11, 1).into()), Some(3));
+    /// assert_eq!(doc.link_at((1, 1).into()), None);
+    /// let nav = &[(cursive_markup::LandmarkKind::Navigation, 0..2)];
+    /// assert_eq!(doc.landmarks(), nav);
+    ///
+    /// // A landmark that continues in the next column covers all rows.
+    /// let html = "<p>a</p><nav><p>b</p><p>c</p></nav>";
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// view.set_columns(2);
+    /// view.layout((20, 3).into());
+    /// let doc = view.document().unwrap();
+    /// assert_eq!(doc.size(), (12, 3).into());
+    /// let nav = &[(cursive_markup::LandmarkKind::Navigation, 0..3)];
+    /// assert_eq!(doc.landmarks(), nav);
+    /// ```
+    ///
+    /// [`RenderedDocument::set_line_style`]: struct.RenderedDocument.html#method.set_line_style
+    /// [`document`]: #method.document
+    pub fn set_columns(&mut self, columns: usize) {
+        let columns = std::cmp::max(columns, 1);
+        if columns != self.columns {
+            self.columns = columns;
+            self.invalidate(true);
+        }
+    }
+
+    /// Sets whether all links with the same target as the focused link should be highlighted.
+    ///
+    /// If this option is enabled, links with the same target as the focused link are drawn with
+    /// the inactive highlight color while the view is focused.  Per default, only the focused
+    /// link is highlighted.
+    pub fn set_highlight_same_target(&mut self, highlight: bool) {
+        self.highlight_same_target = highlight;
+    }
+
+    /// Sets whether links can be focused by typing the start of their text.
+    ///
+    /// If this option is enabled, the view focuses the next link whose text starts with the typed
+    /// printable characters, ignoring the case.  The typed characters are reset if no character
+    /// is typed for one second.  Typing the same character repeatedly cycles through the links
+    /// that start with this character.  The events are only consumed if a matching link is found,
+    /// so other characters still reach the global shortcuts.  As this conflicts with global
+    /// shortcuts for printable characters that match a link, it is disabled per default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::Event;
+    /// use cursive_core::View as _;
+    ///
+    /// let html = r#"<a href="1">apple</a> <a href="2">banana</a> <a href="3">avocado</a>"#;
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// view.set_quick_find(true);
+    /// view.layout((80, 10).into());
+    ///
+    /// assert!(view.on_event(Event::Char('a')).is_consumed());
+    /// assert_eq!(view.focused_index(), Some(2));
+    /// assert!(view.on_event(Event::Char('a')).is_consumed());
+    /// assert_eq!(view.focused_index(), Some(0));
+    /// assert!(!view.on_event(Event::Char('x')).is_consumed());
+    /// ```
+    pub fn set_quick_find(&mut self, quick_find: bool) {
+        self.quick_find = quick_find;
+    }
+
+    /// Sets whether links can be selected by pressing their access key without the Alt key.
+    ///
+    /// Links with an access key, for example from the `accesskey` attribute of an HTML link, are
+    /// always focused and selected if the access key is pressed together with the Alt key.  If
+    /// this option is enabled, pressing the access key alone selects the link too.  Access keys
+    /// take precedence over [quick find][`set_quick_find`], so quick find cannot be started with
+    /// a character that is used as an access key.  If multiple links have the same access key,
+    /// the first link in the document is selected.  As this conflicts with global shortcuts for
+    /// printable characters, it is disabled per default.
+    ///
+    /// [`set_quick_find`]: #method.set_quick_find
+    pub fn set_plain_access_keys(&mut self, plain_access_keys: bool) {
+        self.plain_access_keys = plain_access_keys;
+    }
+
+    /// Sets whether the view should request the full height of the document during the layout.
+    ///
+    /// If this option is disabled, the height that is requested by the view is limited to the
+    /// height of the size constraint so that the view does not take more space than available.
+    /// The full size of the document is still available with [`content_size`][].  Per default,
+    /// the full height is requested.
+    ///
+    /// [`content_size`]: #method.content_size
+    pub fn set_report_full_height(&mut self, full_height: bool) {
+        self.report_full_height = full_height;
+    }
+
+    /// Sets the text that is displayed if the rendered document is empty.
+    ///
+    /// If a text is set and the document does not contain any lines, the text is displayed with
+    /// the secondary palette color instead, for example to indicate that the content is still
+    /// loading.  Per default, an empty document is displayed as an empty view.
+    pub fn set_empty_text(&mut self, text: Option<String>) {
+        self.empty_text = text;
+    }
+
+    /// Sets the base URL that relative link targets are resolved against.
+    ///
+    /// *Requires the `url` feature.  Note that this feature requires a newer Rust version than
+    /// the rest of this crate, see the readme.*
+    ///
+    /// If a base URL is set, the link targets that are passed to the callbacks are resolved
+    /// against it, see [`Url::join`][].  Absolute targets are not changed, and in-page links that
+    /// start with `#` are passed to the callbacks as they are.  All other relative targets, for
+    /// example queries that start with `?`, are resolved.  Per default, no base URL is set and the
+    /// targets are passed as they are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, EventResult, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let selected = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    /// let html = "<a href='page.html'>a</a> <a href='?q=1'>b</a> <a href='#top'>c</a>";
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// let targets = selected.clone();
+    /// view.on_link_select(move |_, target| targets.borrow_mut().push(target.to_owned()));
+    /// view.set_base_url("https://example.org/docs/index.html").unwrap();
+    /// view.layout((80, 10).into());
+    ///
+    /// let mut siv = cursive_core::Cursive::new();
+    /// for _ in 0..3 {
+    ///     if let EventResult::Consumed(Some(callback)) = view.on_event(Event::Key(Key::Enter)) {
+    ///         callback(&mut siv);
+    ///     }
+    ///     view.on_event(Event::Key(Key::Right));
+    /// }
+    /// assert_eq!(
+    ///     *selected.borrow(),
+    ///     vec![
+    ///         "https://example.org/docs/page.html",
+    ///         "https://example.org/docs/index.html?q=1",
+    ///         "#top",
+    ///     ],
+    /// );
+    /// ```
+    ///
+    /// [`Url::join`]: https://docs.rs/url/latest/url/struct.Url.html#method.join
+    #[cfg(feature = "url")]
+    pub fn set_base_url(&mut self, base_url: &str) -> Result<(), url::ParseError> {
+        self.base_url = Some(url::Url::parse(base_url)?);
+        Ok(())
+    }
+
+    /// Sets whether the text outside of the focused link should be dimmed.
+    ///
+    /// If this option is enabled and the view is focused, all text except for the focused link is
+    /// drawn with the tertiary palette color so that the focused link stands out.  Per default,
+    /// the text keeps its color.
+    pub fn set_dim_unfocused(&mut self, dim: bool) {
+        self.dim_unfocused = dim;
+    }
+
+    /// Sets whether the links of this view can be focused and selected.
+    ///
+    /// If this option is disabled, the view does not take focus, ignores all events and does not
+    /// highlight links, so it can be used to display static content.  Per default, the view is
+    /// interactive.
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+    }
+
+    /// Sets the order in which the links are traversed with the arrow keys.
+    ///
+    /// Per default, the links are traversed in document order, i. e. in the order in which they
+    /// were produced by the renderer.  If the visual order of the rendered document differs from
+    /// the document order, [`NavigationOrder::VisualOrder`][] can be used to traverse the links by
+    /// their position instead.
+    ///
+    /// [`NavigationOrder::VisualOrder`]: enum.NavigationOrder.html#variant.VisualOrder
+    pub fn set_navigation_order(&mut self, order: NavigationOrder) {
+        self.navigation_order = order;
+        if let Some(doc) = &mut self.doc {
+            doc.link_handler.set_order(order);
+        }
+    }
+
+    /// Sets the mouse clicks that select a link.
+    ///
+    /// A click with the left mouse button on a link always focuses the link.  With
+    /// [`MouseActivation::SingleClick`][], it also selects the link, i. e. it triggers the
+    /// [`on_link_select`][] callback like the Enter key.  With [`MouseActivation::DoubleClick`][],
+    /// the link is only selected by a double click.  Per default, a double click is required to
+    /// avoid accidental navigation.
+    ///
+    /// [`MouseActivation::SingleClick`]: enum.MouseActivation.html#variant.SingleClick
+    /// [`MouseActivation::DoubleClick`]: enum.MouseActivation.html#variant.DoubleClick
+    /// [`on_link_select`]: #method.on_link_select
+    pub fn set_mouse_activation(&mut self, activation: MouseActivation) {
+        self.mouse_activation = activation;
+    }
+
+    /// Sets whether [`search_next`][] should focus a link that contains the match.
+    ///
+    /// If this option is enabled and the match found by [`search_next`][] overlaps a link, this
+    /// link is focused so that it can be selected with the Enter key.  Like for a focus change
+    /// caused by a key press, the [`on_link_focus`][] and [`on_link_description`][] callbacks are
+    /// triggered, but only when the view consumes the next event.  Per default, this option is
+    /// disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, EventResult, Key};
+    /// use cursive_core::View as _;
+    ///
+    /// let focused = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    /// let html = r#"<p><a href="a">a</a> <a href="b">b</a> <a href="c">c</a></p>"#;
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// let targets = focused.clone();
+    /// view.on_link_focus(move |_, target| targets.borrow_mut().push(target.to_owned()));
+    /// view.set_search_focuses_links(true);
+    /// view.layout((80, 10).into());
+    ///
+    /// view.search_next("c");
+    /// let mut siv = cursive_core::Cursive::new();
+    /// match view.on_event(Event::Key(Key::Left)) {
+    ///     EventResult::Consumed(Some(callback)) => callback(&mut siv),
+    ///     _ => panic!("missing callback"),
+    /// }
+    /// assert_eq!(*focused.borrow(), vec!["c", "b"]);
+    /// ```
+    ///
+    /// [`search_next`]: #method.search_next
+    /// [`on_link_focus`]: #method.on_link_focus
+    /// [`on_link_description`]: #method.on_link_description
+    pub fn set_search_focuses_links(&mut self, focus: bool) {
+        self.search_focuses_links = focus;
+    }
+
+    /// Searches the next occurrence of the given string in the rendered document.
+    ///
+    /// The search starts after the previous match (or at the start of the document) and is
+    /// case-sensitive.  Matches that span multiple lines are not found.  If a match is found, its
+    /// position is returned.  If the view has not been rendered yet or if there are no further
+    /// matches, `None` is returned and the next search starts at the start of the document again.
+    pub fn search_next(&mut self, query: &str) -> Option<cursive_core::XY<usize>> {
+        let doc = self.doc.as_mut()?;
+        let found = doc.find(query, self.last_match);
+        self.last_match = found.map(|(pos, _)| pos);
+        let (pos, width) = found?;
+        if self.search_focuses_links {
+            let link = doc.link_handler.links.iter().position(|link| {
+                link.position.y == pos.y
+                    && link.position.x < pos.x + width
+                    && pos.x < link.position.x + link.width
+            });
+            if let Some(link) = link {
+                let changed = doc.link_handler.focus != link;
+                doc.link_handler.focus = link;
+                self.queue_focus_changed();
+                if changed {
+                    if let cursive_core::event::EventResult::Consumed(Some(callback)) =
+                        self.focus_changed_result()
+                    {
+                        self.pending_callbacks.push(callback);
+                    }
+                }
+            }
+        }
+        Some(pos)
+    }
+
+    /// Sets replacements for text effects that are not supported by the terminal.
+    ///
+    /// When drawing the document, each effect of the given list is removed from the style of the
+    /// text and replaced with the corresponding style, for example to display italic text as
+    /// underlined text.  The replacements are applied in the given order, so a replacement can
+    /// introduce an effect that is replaced by a later entry.  Per default, no replacements are
+    /// applied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::theme::{Effect, Style};
+    ///
+    /// let mut view = cursive_markup::MarkupView::html("<p><em>Hello</em></p>");
+    /// view.set_effect_fallbacks(vec![
+    ///     (Effect::Italic, Effect::Underline.into()),
+    ///     (Effect::Underline, Effect::Bold.into()),
+    /// ]);
+    /// ```
+    pub fn set_effect_fallbacks(&mut self, fallbacks: Vec<(theme::Effect, theme::Style)>) {
+        self.effect_fallbacks = fallbacks;
+    }
+
+    /// Sets the style for links with the given target scheme.
+    ///
+    /// The scheme is the part of the link target before the first colon, for example `mailto` or
+    /// `https`, and is compared case-insensitively.  In-page links that start with `#` use the
+    /// scheme `#`, and all other links without a scheme use the empty scheme.  The style is
+    /// combined with the style of the link text.  Links with a scheme that does not have a style
+    /// keep their default style.
+    pub fn set_link_style_for_scheme(&mut self, scheme: &str, style: theme::Style) {
+        self.scheme_styles.insert(scheme.to_lowercase(), style);
+    }
+
+    /// Sets whether the focused link should be kept if the view loses and regains focus.
+    ///
+    /// If this option is enabled, the previously focused link is focused again when the view
+    /// regains focus.  Otherwise, the first or last visible link (depending on the direction from
+    /// which the view is entered) is focused every time the view takes focus.  If no link is
+    /// visible or if the view has not been drawn yet, the first or last link of the document is
+    /// focused instead.  Per default, this option is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::direction::Direction;
+    /// use cursive_core::View as _;
+    ///
+    /// let html = (0..10)
+    ///     .map(|i| format!("<p><a href='{0}'>link {0}</a></p>", i))
+    ///     .collect::<String>();
+    /// let mut view = cursive_markup::MarkupView::html(&html);
+    /// view.set_remember_focus(false);
+    /// let size = view.required_size((20, usize::MAX).into());
+    /// view.layout(size);
+    ///
+    /// // Draw the rows 6 to 8, like a scroll view does.
+    /// let theme = Default::default();
+    /// let backend = cursive_core::backend::Dummy::init();
+    /// let printer = cursive_core::Printer::new((20, 3), &theme, &*backend)
+    ///     .content_offset((0, 6))
+    ///     .inner_size(size);
+    /// view.draw(&printer);
+    ///
+    /// assert!(view.take_focus(Direction::none()));
+    /// assert_eq!(view.focused_link_text().as_deref(), Some("link 3"));
+    /// assert!(view.take_focus(Direction::down()));
+    /// assert_eq!(view.focused_link_text().as_deref(), Some("link 4"));
+    /// ```
+    pub fn set_remember_focus(&mut self, remember: bool) {
+        self.remember_focus = remember;
+    }
+
+    /// Renders the document with the given width, regardless of the size of the view.
+    ///
+    /// This can be used to export the document with a fixed width.  The result is cached
+    /// separately from the document that is displayed by the view, so calling this method does
+    /// not affect the view.  The maximum width and the padding of the view are not applied.
+    pub fn render_at_width(&mut self, width: usize) -> &RenderedDocument {
+        let cached = self
+            .export_doc
+            .as_ref()
+            .map(|doc| doc.constraint.x == width)
+            .unwrap_or_default();
+        if !cached {
+            self.export_doc = Some(self.render_document((width, usize::MAX).into()));
+        }
+        self.export_doc
+            .as_ref()
+            .expect("export document not rendered")
+    }
+
+    /// Returns whether the document has to be rendered before the view can be drawn.
+    ///
+    /// This is the case if the view has not been rendered yet or if the content or the settings
+    /// of the view have been changed since the last rendering, including settings that change
+    /// the [`effective_constraint`][] for the last size of the view.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    ///
+    /// let mut view = cursive_markup::MarkupView::html("<p>Hello world</p>");
+    /// assert!(view.needs_render());
+    /// view.layout((80, 24).into());
+    /// assert!(!view.needs_render());
+    ///
+    /// view.set_maximum_width(60);
+    /// assert!(view.needs_render());
+    /// view.layout((80, 24).into());
+    /// assert!(!view.needs_render());
+    ///
+    /// view.set_padding(30, 0);
+    /// assert!(view.needs_render());
+    /// ```
+    ///
+    /// [`effective_constraint`]: #method.effective_constraint
+    pub fn needs_render(&self) -> bool {
+        match (&self.doc, self.last_constraint) {
+            (Some(doc), Some(constraint)) => {
+                self.effective_constraint(constraint).x != doc.constraint.x
+            }
+            _ => true,
+        }
+    }
+
+    /// Renders the document for a view with the given width.
+    ///
+    /// The view is typically rendered during the first layout.  This method can be used to render
+    /// the document in advance so that the cached document can be reused by the first layout with
+    /// the same width.  The padding and the maximum width are applied in the same way as during
+    /// the layout.
+    pub fn prerender(&mut self, width: usize) {
+        self.render((width, usize::MAX).into());
+    }
+
+    /// Returns whether a layout with the given width would reuse the cached document.
+    ///
+    /// The padding and the maximum width are applied to the given width in the same way as
+    /// during the layout, see [`effective_constraint`][].  If the view has not been rendered yet,
+    /// `false` is returned.
+    ///
+    /// [`effective_constraint`]: #method.effective_constraint
+    pub fn is_cache_valid_for(&self, width: usize) -> bool {
+        let width = self.effective_constraint((width, 0).into()).x;
+        self.doc
+            .as_ref()
+            .map(|doc| doc.constraint.x == width)
+            .unwrap_or_default()
+    }
+
+    /// Returns the rendered document that is displayed by the view.
+    ///
+    /// The document does not include the padding and the hint of the view.  If the view has not
+    /// been rendered yet, `None` is returned.
+    pub fn document(&self) -> Option<&RenderedDocument> {
+        self.doc.as_ref()
+    }
+
+    /// Returns statistics about the last rendering of the document for the view.
+    ///
+    /// If the view has not been rendered yet, `None` is returned.  Documents that are rendered
+    /// with [`render_at_width`][] are not taken into account.
+    ///
+    /// [`render_at_width`]: #method.render_at_width
+    pub fn last_render_stats(&self) -> Option<RenderStats> {
+        self.render_stats
+    }
+
+    /// Returns the size of the rendered document.
+    ///
+    /// The width of the document is the width of its longest line, so it can be smaller than the
+    /// width of the view.  For the HTML renderer, it does not exceed the width of the
+    /// [`effective_constraint`][] unless wrapping preformatted lines is disabled, see
+    /// [`html::Renderer::set_pre_wrap`][].  If the view has not been rendered yet, `None` is
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    ///
+    /// // The text fills the first line exactly.
+    /// let mut view = cursive_markup::MarkupView::html("<p>aaaa bbbb cccc</p>");
+    /// view.layout((9, 10).into());
+    /// assert_eq!(view.content_size(), Some((9, 2).into()));
+    ///
+    /// // The HTML renderer uses a minimum width of five columns.
+    /// view.layout((3, 10).into());
+    /// assert_eq!(view.effective_constraint((3, 10).into()).x, 5);
+    /// assert_eq!(view.content_size(), Some((4, 3).into()));
+    /// ```
+    ///
+    /// [`effective_constraint`]: #method.effective_constraint
+    /// [`html::Renderer::set_pre_wrap`]: html/struct.Renderer.html#method.set_pre_wrap
+    pub fn content_size(&self) -> Option<cursive_core::XY<usize>> {
+        self.doc.as_ref().map(RenderedDocument::size)
+    }
+
+    /// Returns the focused link as a terminal hyperlink.
+    ///
+    /// The returned string contains the text of the focused link, wrapped in the OSC 8 escape
+    /// sequences for terminal hyperlinks, see [`osc8_hyperlink`][].  If the view has not been
+    /// rendered yet, if there are no links or if a section toggle is focused, `None` is returned.
+    ///
+    /// [`osc8_hyperlink`]: fn.osc8_hyperlink.html
+    pub fn focused_link_osc8(&self) -> Option<String> {
+        let doc = self.doc.as_ref()?;
+        let link = doc
+            .link_handler
+            .links
+            .get(doc.link_handler.focus)
+            .filter(|link| link.section.is_none())?;
+        Some(osc8_hyperlink(
+            &link.target,
+            &doc.link_text(doc.link_handler.focus),
+        ))
+    }
+
+    /// Returns the table of contents of the rendered document.
+    ///
+    /// The table of contents contains the level, the text and the line of all headings of the
+    /// document, see [`RenderedDocument::headings`][].  If the view has not been rendered yet, an
+    /// empty list is returned.
+    ///
+    /// [`RenderedDocument::headings`]: struct.RenderedDocument.html#method.headings
+    pub fn table_of_contents(&self) -> Vec<(u8, String, usize)> {
+        self.doc
+            .as_ref()
+            .map(|doc| doc.headings.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the visible text and the target of all links in the rendered document.
+    ///
+    /// The links are returned in document order.  The toggles of collapsible sections are not
+    /// links, so they are not included.  If the view has not been rendered yet, an empty list is
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    ///
+    /// let html = "<details><summary>More</summary><a href='a'>A</a></details>";
+    /// let mut view = cursive_markup::MarkupView::html(html);
+    /// view.layout((80, 10).into());
+    /// assert_eq!(view.links(), vec![]);
+    /// assert_eq!(view.link_targets(), Vec::<&str>::new());
+    /// assert_eq!(view.focused_index(), None);
+    /// assert_eq!(view.focused_link_text(), None);
+    /// ```
+    pub fn links(&self) -> Vec<(String, String)> {
+        let doc = match &self.doc {
+            Some(doc) => doc,
+            None => return Vec::new(),
+        };
+        let indices = doc.public_link_indices();
+        let mut links: Vec<_> = doc
+            .link_handler
+            .links
+            .iter()
+            .filter(|link| link.section.is_none())
+            .map(|link| (String::new(), link.target.clone()))
+            .collect();
+        for element in doc.lines.iter().flatten() {
+            if let Some(idx) = element.link_idx.and_then(|link_idx| indices[link_idx]) {
+                links[idx].0.push_str(&element.text);
+            }
+        }
+        links
+    }
+
+    /// Returns the targets of all links in the rendered document.
+    ///
+    /// The targets are returned in document order, so the index of a target is the index of the
+    /// link, see [`focused_index`][].  The toggles of collapsible sections are not included.  In
+    /// contrast to the targets passed to the callbacks, these targets are not normalized or
+    /// transformed.  If the view has not been rendered yet, an empty list is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    ///
+    /// let mut view = cursive_markup::MarkupView::html("<a href='a'>A</a> <a href='b'>B</a>");
+    /// view.layout((80, 10).into());
+    /// assert_eq!(view.link_targets(), vec!["a", "b"]);
+    /// assert_eq!(view.focused_index(), Some(0));
+    /// ```
+    ///
+    /// [`focused_index`]: #method.focused_index
+    pub fn link_targets(&self) -> Vec<&str> {
+        self.doc
+            .iter()
+            .flat_map(|doc| doc.link_handler.links.iter())
+            .filter(|link| link.section.is_none())
+            .map(|link| link.target.as_str())
+            .collect()
+    }
+
+    /// Returns the index of the focused link in document order.
+    ///
+    /// The index does not take the toggles of collapsible sections into account.  If the view has
+    /// not been rendered yet, if there are no links or if a section toggle is focused, `None` is
+    /// returned.
+    pub fn focused_index(&self) -> Option<usize> {
+        let doc = self.doc.as_ref()?;
+        doc.link_handler.links.get(doc.link_handler.focus)?;
+        doc.public_link_index(doc.link_handler.focus)
+    }
+
+    /// Scrolls to the target of the focused in-page link.
+    ///
+    /// If the target of the focused link starts with `#` and the rendered document contains an
+    /// anchor with the remaining name, see [`RenderedDocument::anchor`][], the view scrolls to the
+    /// line of the anchor and focuses the first link on or after this line.  This can be used to
+    /// jump between footnote references and definitions that link to each other.  Returns `true`
+    /// if the anchor was found.
+    ///
+    /// The line of the anchor is reported as the important area of the view until the next event.
+    /// A [`ScrollView`][] only scrolls to the important area after an event has been consumed,
+    /// so if this method is called outside of an event handler, you have to call
+    /// [`ScrollView::scroll_to_important_area`][] to show the anchor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::view::View as _;
+    ///
+    /// let html = r##"<p><a href="#note">note</a></p><p>a</p><p>b</p><p id="note">Note</p>"##;
+    /// let view = cursive_markup::MarkupView::html(html);
+    /// let mut view = cursive_core::views::ScrollView::new(view);
+    /// view.layout((20, 3).into());
+    /// assert_eq!(view.content_viewport().top(), 0);
+    ///
+    /// assert!(view.get_inner_mut().follow_fragment());
+    /// view.scroll_to_important_area();
+    /// assert_eq!(view.content_viewport().top(), 4);
+    /// ```
+    ///
+    /// [`RenderedDocument::anchor`]: struct.RenderedDocument.html#method.anchor
+    /// [`ScrollView`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html
+    /// [`ScrollView::scroll_to_important_area`]: https://docs.rs/cursive/latest/cursive/views/struct.ScrollView.html#method.scroll_to_important_area
+    pub fn follow_fragment(&mut self) -> bool {
+        let doc = match &mut self.doc {
+            Some(doc) => doc,
+            None => return false,
+        };
+        let line = doc
+            .link_handler
+            .links
+            .get(doc.link_handler.focus)
+            .and_then(|link| link.target.strip_prefix('#'))
+            .and_then(|name| doc.anchor(name));
+        let line = match line {
+            Some(line) => line,
+            None => return false,
+        };
+        let link = doc
+            .link_handler
+            .links
+            .iter()
+            .enumerate()
+            .filter(|(_, link)| link.position.y >= line)
+            .min_by_key(|(_, link)| (link.position.y, link.position.x))
+            .map(|(idx, _)| idx);
+        if let Some(link) = link {
+            doc.link_handler.focus = link;
+        }
+        self.fragment_line = Some(line);
+        self.queue_focus_changed();
+        true
+    }
+
+    /// Returns the area of the focused link, including all parts of a link that spans multiple
+    /// rows.
+    ///
+    /// The parts of a link are the consecutive links with the same target that directly follow
+    /// each other, either in the same row or at the start of the next row.  The area is relative
+    /// to the view and includes the padding.  If the view has not been rendered yet or if there
+    /// are no links, `None` is returned.
+    pub fn focused_link_area(&self) -> Option<cursive_core::Rect> {
+        let doc = self.doc.as_ref()?;
+        let (start, end) = doc.link_handler.focused_link_parts()?;
+        let links = &doc.link_handler.links[start..=end];
+        let left = links.iter().map(|link| link.position.x).min()?;
+        let right = links
+            .iter()
+            .map(|link| link.position.x + link.width.max(1))
+            .max()?;
+        let top = links[0].position.y;
+        let bottom = links[links.len() - 1].position.y;
+        Some(cursive_core::Rect::from_corners(
+            self.padding + (left, top),
+            self.padding + (right - 1, bottom),
+        ))
+    }
+
+    /// Returns the line of the rendered document that contains the focused link.
+    ///
+    /// This can be used to keep an external scroll bar or status line in sync with the link
+    /// navigation.  The line does not include the padding of the view, see [`set_padding`][].
+    /// If the view has not been rendered yet or if there are no links, `None` is returned.
+    ///
+    /// [`set_padding`]: #method.set_padding
+    pub fn focused_link_row(&self) -> Option<usize> {
+        let doc = self.doc.as_ref()?;
+        let link = doc.link_handler.links.get(doc.link_handler.focus)?;
+        Some(link.position.y)
+    }
+
+    /// Returns the visible text of the focused link.
+    ///
+    /// If the link is split across multiple lines, the text of all parts is concatenated.  If the
+    /// view has not been rendered yet, if there are no links or if a section toggle is focused,
+    /// `None` is returned.
+    pub fn focused_link_text(&self) -> Option<String> {
+        let doc = self.doc.as_ref()?;
+        doc.link_handler
+            .links
+            .get(doc.link_handler.focus)
+            .filter(|link| link.section.is_none())?;
+        Some(doc.link_text(doc.link_handler.focus))
+    }
+
+    /// Returns the target of the link that would be focused if the focus was moved in the given
+    /// direction, without changing the focus.
+    ///
+    /// This uses the same logic as the arrow keys, so it can be used to preview the effect of a
+    /// key press, for example in a tooltip.  If the view has not been rendered yet, if there are
+    /// no links, if the focus cannot be moved in the given direction or if the focus would be
+    /// moved to a section toggle, `None` is returned.
+    pub fn peek_focus(&self, direction: cursive_core::direction::Absolute) -> Option<&str> {
+        let doc = self.doc.as_ref()?;
+        let link = doc.link_handler.find_focus(direction)?;
+        let link = &doc.link_handler.links[link];
+        link.section.map_or(Some(&link.target), |_| None)
+    }
+
+    /// Returns the description of the focused link.
+    ///
+    /// The description is set by the renderer, for example from the `aria-label` attribute of an
+    /// HTML link.  If the view has not been rendered yet, if there are no links or if the focused
+    /// link does not have a description, `None` is returned.
+    pub fn focused_link_description(&self) -> Option<&str> {
+        let doc = self.doc.as_ref()?;
+        let link = doc.link_handler.links.get(doc.link_handler.focus)?;
+        link.description.as_deref()
+    }
+
+    fn link_callback(
+        &self,
+        callback: &Option<rc::Rc<LinkCallback>>,
+        target: String,
+    ) -> Option<cursive_core::event::Callback> {
+        let target = if self.normalize_link_targets {
+            normalize_link_target(&target)
+        } else {
+            target
+        };
+        #[cfg(feature = "url")]
+        let target = match &self.base_url {
+            Some(base_url) if !target.starts_with('#') => {
+                base_url.join(&target).map(String::from).unwrap_or(target)
+            }
+            _ => target,
+        };
+        let target = match &self.link_target_transform {
+            Some(transform) => transform(&target),
+            None => target,
+        };
+        callback
+            .clone()
+            .map(|f| cursive_core::event::Callback::from_fn(move |s| f(s, &target)))
+    }
+
+    fn handle_event(
+        &mut self,
+        event: cursive_core::event::Event,
+    ) -> cursive_core::event::EventResult {
+        use cursive_core::direction::{Absolute, Relative};
+        use cursive_core::event::{Event, EventResult, Key};
+
+        if !self.interactive {
+            return EventResult::Ignored;
+        }
+
+        let access_key = match event {
+            Event::AltChar(c) => Some(c),
+            Event::Char(c) if self.plain_access_keys => Some(c),
+            _ => None,
+        };
+        if let Some(c) = access_key {
+            if let Some(doc) = self.doc.as_mut() {
+                let link_handler = &mut doc.link_handler;
+                let link = link_handler
+                    .links
+                    .iter()
+                    .position(|link| link.access_key == Some(c));
+                if let Some(link) = link {
+                    link_handler.focus = link;
+                    return self.select_focused_link();
+                }
+            }
+        }
+
+        let quick_find_focus = match event {
+            Event::Char(c) if self.quick_find && !c.is_control() => self.quick_find_link(c),
+            _ => None,
+        };
+
+        if let Event::Mouse {
+            offset,
+            position,
+            event,
+        } = event
+        {
+            return self.handle_mouse_event(position.checked_sub(offset), event);
+        }
+
+        let doc = match self.doc.as_mut() {
+            Some(doc) if !doc.link_handler.links.is_empty() => doc,
+            _ => return EventResult::Ignored,
+        };
+        let link_handler = &mut doc.link_handler;
+
+        let focus_changed = match event {
+            Event::Key(Key::Left) => link_handler.move_focus(Absolute::Left),
+            Event::Key(Key::Right) => link_handler.move_focus(Absolute::Right),
+            Event::Key(Key::Up) => link_handler.move_focus(Absolute::Up),
+            Event::Key(Key::Down) => link_handler.move_focus(Absolute::Down),
+            Event::Key(Key::Home) => link_handler.move_focus_line_edge(Relative::Front),
+            Event::Key(Key::End) => link_handler.move_focus_line_edge(Relative::Back),
+            Event::Key(Key::Tab) => link_handler.focus_next(self.wrap_focus),
+            Event::Shift(Key::Tab) => link_handler.focus_prev(self.wrap_focus),
+            _ => match quick_find_focus {
+                Some(focus) if focus != link_handler.focus => {
+                    link_handler.focus = focus;
+                    true
+                }
+                _ => false,
+            },
+        };
+
+        if focus_changed {
+            self.focus_changed_result()
+        } else if quick_find_focus.is_some() {
+            EventResult::Consumed(None)
+        } else if self.activate_key.as_ref() == Some(&event) {
+            let link = &link_handler.links[link_handler.focus];
+            if link.section.is_some() {
+                return EventResult::Ignored;
+            }
+            let target = link.target.clone();
+            EventResult::Consumed(self.link_callback(&self.on_link_activate, target))
+        } else if event == Event::Key(Key::Enter) {
+            self.select_focused_link()
+        } else if event == Event::Ctrl(Key::Enter) {
+            let link = &link_handler.links[link_handler.focus];
+            if link.section.is_some() {
+                return EventResult::Ignored;
+            }
+            let target = link.target.clone();
+            EventResult::Consumed(self.link_callback(&self.on_link_select_in_new, target))
+        } else {
+            EventResult::Ignored
+        }
+    }
+
+    /// Handles a mouse event at the given position relative to the view.
+    ///
+    /// A click with the left mouse button on a link focuses the link and selects it, depending
+    /// on the [`MouseActivation`][].
+    ///
+    /// [`MouseActivation`]: enum.MouseActivation.html
+    fn handle_mouse_event(
+        &mut self,
+        position: Option<cursive_core::XY<usize>>,
+        event: cursive_core::event::MouseEvent,
+    ) -> cursive_core::event::EventResult {
+        use cursive_core::event::{EventResult, MouseButton, MouseEvent};
+
+        let link = match (self.doc.as_ref(), position) {
+            (Some(doc), Some(position)) => position.checked_sub(self.padding).and_then(|pos| {
+                doc.link_handler.links.iter().position(|link| {
+                    link.position.y == pos.y
+                        && link.position.x <= pos.x
+                        && pos.x < link.position.x + link.width
+                })
+            }),
+            _ => None,
+        };
+        let link = match link {
+            Some(link) => link,
+            None => return EventResult::Ignored,
+        };
+
+        match event {
+            MouseEvent::Press(MouseButton::Left) => EventResult::Consumed(None),
+            MouseEvent::Release(MouseButton::Left) => {
+                let now = time::Instant::now();
+                let select = match self.mouse_activation {
+                    MouseActivation::SingleClick => true,
+                    MouseActivation::DoubleClick => match self.last_click {
+                        Some((last, time)) => {
+                            last == link && now.duration_since(time) <= DOUBLE_CLICK_TIMEOUT
+                        }
+                        None => false,
+                    },
+                };
+                self.last_click = if select { None } else { Some((link, now)) };
+
+                let link_handler = &mut self.doc.as_mut().expect("checked above").link_handler;
+                let result = if link_handler.focus != link {
+                    link_handler.focus = link;
+                    self.focus_changed_result()
+                } else {
+                    EventResult::Consumed(None)
+                };
+                if select {
+                    result.and(self.select_focused_link())
+                } else {
+                    result
+                }
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+
+    /// Returns the callbacks that are triggered if the focused link has been changed.
+    fn focus_changed_result(&self) -> cursive_core::event::EventResult {
+        use cursive_core::event::EventResult;
+
+        let doc = match &self.doc {
+            Some(doc) => doc,
+            None => return EventResult::Consumed(None),
+        };
+        let focus = doc.link_handler.focus;
+        let link = &doc.link_handler.links[focus];
+        let target = link.target.clone();
+        let near_bottom = link.position.y + self.near_bottom_threshold + 1 >= doc.lines.len();
+        let result =
+            if link.section.is_some() {
+                // Section toggles are not links, so we don't report them to the callbacks.
+                EventResult::Consumed(None)
+            } else {
+                let description = link
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| doc.link_text(focus));
+                EventResult::Consumed(self.link_callback(&self.on_link_focus, target)).and(
+                    EventResult::Consumed(self.on_link_description.clone().map(|f| {
+                        cursive_core::event::Callback::from_fn(move |s| f(s, &description))
+                    })),
+                )
+            };
+        match &self.on_near_bottom {
+            Some(f) if near_bottom => result.and(EventResult::Consumed(Some(f.clone()))),
+            _ => result,
+        }
+    }
+
+    /// Selects the focused link, or opens or closes the section if it is a section toggle.
+    fn select_focused_link(&mut self) -> cursive_core::event::EventResult {
+        use cursive_core::event::EventResult;
+
+        let doc = match &self.doc {
+            Some(doc) => doc,
+            None => return EventResult::Ignored,
+        };
+        let link = &doc.link_handler.links[doc.link_handler.focus];
+        if let Some(section) = link.section {
+            let open = self
+                .open_sections
+                .get(&section)
+                .copied()
+                .unwrap_or(doc.sections[section].1);
+            self.open_sections.insert(section, !open);
+            self.invalidate(true);
+            return EventResult::Consumed(None);
+        }
+        let target = link.target.clone();
+        EventResult::Consumed(self.link_callback(&self.on_link_select, target))
+    }
+
+    /// Adds the given character to the quick find prefix and returns the next link in navigation
+    /// order whose text starts with the prefix.
+    ///
+    /// If the prefix is continued, the search starts with the focused link.  If there is no such
+    /// link, the prefix is reset to the given character.  A new prefix is searched after the
+    /// focused link so that typing the same character repeatedly cycles through the links.
+    fn quick_find_link(&mut self, c: char) -> Option<usize> {
+        let now = time::Instant::now();
+        let expired = self
+            .quick_find_time
+            .map(|time| now.duration_since(time) > QUICK_FIND_TIMEOUT)
+            .unwrap_or(true);
+        if expired {
+            self.quick_find_prefix.clear();
+        }
+        self.quick_find_time = Some(now);
+        let continued = !self.quick_find_prefix.is_empty();
+        self.quick_find_prefix.extend(c.to_lowercase());
+
+        let doc = self.doc.as_ref()?;
+        let handler = &doc.link_handler;
+        let mut texts = vec![String::new(); handler.links.len()];
+        for element in doc.lines.iter().flatten() {
+            if let Some(idx) = element.link_idx {
+                texts[idx].push_str(&element.text);
+            }
+        }
+        let texts: Vec<_> = texts
+            .iter()
+            .map(|text| text.trim_start().to_lowercase())
+            .collect();
+        let pos = handler.focus_position();
+        let find = |prefix: &str, start: usize| {
+            handler.order[start..]
+                .iter()
+                .chain(&handler.order[..start])
+                .copied()
+                .find(|&idx| handler.links[idx].section.is_none() && texts[idx].starts_with(prefix))
+        };
+        let next = std::cmp::min(pos + 1, handler.order.len());
+        let link = if continued {
+            find(&self.quick_find_prefix, pos)
+        } else {
+            find(&self.quick_find_prefix, next)
+        };
+        if link.is_none() && continued {
+            self.quick_find_prefix = c.to_lowercase().collect();
+            find(&self.quick_find_prefix, next)
+        } else {
+            link
+        }
+    }
+
+    /// Queues the [`on_focus_changed`][] callback if the focused link is not the link that has
+    /// been reported last.
+    ///
+    /// [`on_focus_changed`]: #method.on_focus_changed
+    fn queue_focus_changed(&mut self) {
+        let focus = self.focused_index();
+        // Section toggles are not links, so they are skipped.
+        if focus.is_none() {
+            return;
+        }
+        let old_focus = std::mem::replace(&mut self.reported_focus, focus);
+        if let (Some(f), Some(old), Some(new)) = (&self.on_focus_changed, old_focus, focus) {
+            if old != new {
+                let f = f.clone();
+                self.pending_callbacks
+                    .push(cursive_core::event::Callback::from_fn(move |s| {
+                        f(s, old, new)
+                    }));
+            }
+        }
+    }
+
+    /// Sets the overflow state and queues the [`on_horizontal_overflow`][] callback if it has
+    /// changed.
+    ///
+    /// [`on_horizontal_overflow`]: #method.on_horizontal_overflow
+    fn set_overflow(&mut self, overflow: bool) {
+        self.overflow = overflow;
+        if overflow == std::mem::replace(&mut self.reported_overflow, overflow) {
+            return;
+        }
+        if let Some(f) = &self.on_horizontal_overflow {
+            let f = f.clone();
+            self.pending_callbacks
+                .push(cursive_core::event::Callback::from_fn(move |s| {
+                    f(s, overflow)
+                }));
+        }
+    }
+
+    fn apply_effect_fallbacks(&self, mut style: theme::Style) -> theme::Style {
+        for (effect, fallback) in &self.effect_fallbacks {
+            if style.effects.contains(*effect) {
+                style.effects.remove(*effect);
+                style = style.combine(*fallback);
+            }
+        }
+        style
+    }
+
+    /// Replaces the renderer and resets the state that refers to the previous document.
+    fn replace_content(&mut self, renderer: R, preserve_focus: bool) {
+        self.renderer = renderer;
+        self.appended.clear();
+        self.open_sections.clear();
+        self.last_click = None;
+        self.quick_find_prefix.clear();
+        self.quick_find_time = None;
+        self.invalidate(preserve_focus);
+    }
+
+    /// Removes the cached documents so that the content is rendered again during the next
+    /// layout, optionally keeping the index of the focused link.
+    fn invalidate(&mut self, preserve_focus: bool) {
+        let focus = self.doc.as_ref().map(|doc| doc.link_handler.focus);
+        self.preserved_focus = if preserve_focus {
+            focus.or(self.preserved_focus)
+        } else {
+            self.reported_focus = None;
+            None
+        };
+        self.doc = None;
+        self.export_doc = None;
+        self.last_match = None;
+        self.fragment_line = None;
+    }
+
+    fn render_document(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
+        let column_constraint = if self.columns > 1 {
+            let gaps = COLUMN_GAP * (self.columns - 1);
+            let width = constraint.x.saturating_sub(gaps) / self.columns;
+            (std::cmp::max(width, 1), constraint.y).into()
+        } else {
+            constraint
+        };
+        let mut doc = self.renderer.render(column_constraint);
+        for renderer in &self.appended {
+            doc.extend(renderer.render(column_constraint));
+        }
+        if !doc.sections.is_empty() {
+            doc.hide_sections(|idx, open| self.open_sections.get(&idx).copied().unwrap_or(open));
+        }
+        if let Some((start, end)) = &self.link_markers {
+            doc.add_link_markers(start, end);
+        }
+        if self.columns > 1 {
+            doc.arrange_columns(self.columns, column_constraint.x + COLUMN_GAP);
+            doc.constraint = constraint;
+        }
+        doc.link_handler.set_order(self.navigation_order);
+        doc.link_handler.skip_duplicates = self.skip_duplicate_links;
+        doc
+    }
+
+    /// Returns the constraint that is used to render the document for the given view size.
+    ///
+    /// The padding is subtracted from the size, the width is limited to the maximum width, see
+    /// [`set_maximum_width`][], and it is increased to the minimum width of the renderer, see
+    /// [`Renderer::minimum_width`][] and for example [`html::Renderer::set_minimum_width`][].  If
+    /// reflowing is disabled with [`set_reflow`][], the fixed width of 1000 columns is used
+    /// instead.  The document is only rendered again if this width changes.
+    ///
+    /// This is the constraint that is passed to the renderer, or split into columns if
+    /// [`set_columns`][] is used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut view = cursive_markup::MarkupView::html("<p>Hello world</p>");
+    /// view.set_maximum_width(60);
+    /// assert_eq!(view.effective_constraint((80, 24).into()), (60, 24).into());
+    /// assert_eq!(view.effective_constraint((40, 24).into()), (40, 24).into());
+    ///
+    /// // The HTML renderer uses a minimum width of five columns per default.
+    /// assert_eq!(view.effective_constraint((3, 24).into()), (5, 24).into());
+    /// ```
+    ///
+    /// [`set_maximum_width`]: #method.set_maximum_width
+    /// [`Renderer::minimum_width`]: trait.Renderer.html#method.minimum_width
+    /// [`html::Renderer::set_minimum_width`]: html/struct.Renderer.html#method.set_minimum_width
+    /// [`set_reflow`]: #method.set_reflow
+    /// [`set_columns`]: #method.set_columns
+    pub fn effective_constraint(
+        &self,
+        constraint: cursive_core::XY<usize>,
+    ) -> cursive_core::XY<usize> {
+        let mut constraint = constraint.saturating_sub(self.padding);
+        constraint.x = if !self.reflow {
+            FIXED_WIDTH
+        } else {
+            let width = match self.maximum_width {
+                Some(maximum_width) => std::cmp::min(maximum_width, constraint.x),
+                None => constraint.x,
+            };
+            std::cmp::max(self.renderer.minimum_width(), width)
+        };
+        constraint
+    }
+
+    fn render(&mut self, constraint: cursive_core::XY<usize>) -> cursive_core::XY<usize> {
+        let mut last_focus = 0;
+
+        self.last_constraint = Some(constraint);
+        let view_width = constraint.x.saturating_sub(self.padding.x);
+        let constraint = self.effective_constraint(constraint);
+
+        if let Some(doc) = &self.doc {
+            if constraint.x == doc.constraint.x {
+                let size = doc.size;
+                self.set_overflow(size.x > view_width);
+                return size;
+            }
+            last_focus = doc.link_handler.focus;
+        }
+
+        let start = time::Instant::now();
+        let mut doc = self.render_document(constraint);
+        self.render_stats = Some(RenderStats {
+            duration: start.elapsed(),
+            lines: doc.lines.len(),
+            links: doc.public_link_indices().iter().flatten().count(),
+            width: constraint.x,
+        });
+
+        // TODO: Rendering the document with a different width may lead to links being split up (or
+        // previously split up links being no longer split up).  Ideally, we would adjust the focus
+        // for these changes.
+        if let Some(focus) = self.preserved_focus.take() {
+            let link_count = doc.link_handler.links.len();
+            doc.link_handler.focus = std::cmp::min(focus, link_count.saturating_sub(1));
+        } else if last_focus < doc.link_handler.links.len() {
+            doc.link_handler.focus = last_focus;
+        }
+        let size = doc.size;
+        self.set_overflow(size.x > view_width);
+        self.doc = Some(doc);
+        if self.reported_focus.is_none() {
+            self.reported_focus = self.focused_index();
+        } else {
+            self.queue_focus_changed();
+        }
+        self.last_match = None;
+        size
+    }
+}
+
+impl<R: Renderer + 'static> cursive_core::View for MarkupView<R> {
+    fn draw(&self, printer: &cursive_core::Printer<'_, '_>) {
+        let doc = &self.doc.as_ref().expect("layout not called before draw");
+        // The offset printer keeps its size if the padding is scrolled out of the visible area, so
+        // we compute the size of the document area before applying the offset.
+        let size = printer.size.saturating_sub(self.padding);
+        let printer = &printer.offset(self.padding);
+        let highlighted_target = doc
+            .link_handler
+            .links
+            .get(doc.link_handler.focus)
+            .filter(|link| self.highlight_same_target && link.section.is_none())
+            .filter(|_| self.interactive && printer.focused)
+            .map(|link| &link.target);
+        let dim = self.dim_unfocused
+            && self.interactive
+            && printer.focused
+            && !doc.link_handler.links.is_empty();
+        // Skip the lines outside of the visible area, for example if the view is scrolled.
+        let visible = printer.content_offset.y..printer.content_offset.y + printer.output_size.y;
+        self.visible_lines.set(Some((visible.start, visible.end)));
+        for (y, line) in doc
+            .lines
+            .iter()
+            .enumerate()
+            .skip(visible.start)
+            .take(visible.len())
+        {
+            let line_style = doc.line_styles.get(&y).copied();
+            let mut x = 0;
+            for element in line {
+                let mut style = match line_style {
+                    Some(line_style) => line_style.combine(element.style),
+                    None => element.style,
+                };
+                style = self.apply_effect_fallbacks(style);
+                if dim && element.link_idx != Some(doc.link_handler.focus) {
+                    style = style.combine(theme::ColorStyle::front(theme::PaletteColor::Tertiary));
+                }
+                if let Some(link_idx) = element.link_idx {
+                    let target = &doc.link_handler.links[link_idx].target;
+                    if let Some(scheme_style) = self.scheme_styles.get(&link_scheme(target)) {
+                        style = style.combine(*scheme_style);
+                    }
+                    if highlighted_target == Some(target) {
+                        style = style.combine(theme::ColorStyle::new(
+                            theme::PaletteColor::HighlightInactive,
+                            theme::PaletteColor::View,
+                        ));
+                    }
+                    if self.interactive && printer.focused && doc.link_handler.focus == link_idx {
+                        // We set both the front and the back color so that all elements of the
+                        // focused link are highlighted in the same way, regardless of their own
+                        // style.
+                        style = style.combine(theme::ColorStyle::new(
+                            theme::PaletteColor::Highlight,
+                            theme::PaletteColor::View,
+                        ));
+                    }
+                }
+                printer.with_style(style, |printer| printer.print((x, y), &element.text));
+                x += element.width;
+            }
+            if let Some(line_style) = line_style {
+                let width = size.x.saturating_sub(x);
+                printer.with_style(self.apply_effect_fallbacks(line_style), |printer| {
+                    printer.print_hline((x, y), width, " ")
+                });
+            }
+        }
+
+        if let Some(empty_text) = self.empty_text.as_ref().filter(|_| doc.lines.is_empty()) {
+            printer.with_color(theme::PaletteColor::Secondary.into(), |printer| {
+                printer.print((0, 0), empty_text)
+            });
+        }
+
+        if let Some(hint) = &self.hint {
+            let y = std::cmp::max(doc.lines.len(), size.y.saturating_sub(1));
+            printer.with_color(theme::PaletteColor::Secondary.into(), |printer| {
+                printer.print((0, y), hint)
+            });
+        }
+    }
+
+    fn layout(&mut self, constraint: cursive_core::XY<usize>) {
+        self.render(constraint);
+    }
+
+    fn required_size(&mut self, constraint: cursive_core::XY<usize>) -> cursive_core::XY<usize> {
+        let mut size = self.render(constraint);
+        if let Some(empty_text) = &self.empty_text {
+            if size.y == 0 {
+                size = (empty_text.width(), 1).into();
+            }
+        }
+        let size = size + self.padding;
+        let mut size = match &self.hint {
+            Some(hint) => size.stack_vertical(&(hint.width(), 1).into()),
+            None => size,
+        };
+        if !self.report_full_height {
+            size.y = std::cmp::min(size.y, constraint.y);
+        }
+        size
+    }
+
+    fn take_focus(&mut self, direction: cursive_core::direction::Direction) -> bool {
+        if !self.interactive {
+            return false;
+        }
+        let keep_focus = self.remember_focus && self.has_focused;
+        let visible_lines = self.visible_lines.get();
+        let focused = self
+            .doc
+            .as_mut()
+            .map(|doc| {
+                if keep_focus {
+                    !doc.link_handler.links.is_empty()
+                } else if let Some((start, end)) = visible_lines {
+                    doc.link_handler.take_focus_in(direction, start..end)
+                } else {
+                    doc.link_handler.take_focus(direction)
+                }
+            })
+            .unwrap_or_default();
+        self.has_focused |= focused;
+        if focused {
+            self.queue_focus_changed();
+        }
+        if focused && self.notify_take_focus {
+            let target = self
+                .doc
+                .as_ref()
+                .map(|doc| &doc.link_handler.links[doc.link_handler.focus])
+                .filter(|link| link.section.is_none())
+                .map(|link| link.target.clone());
+            if let Some(target) = target {
+                self.pending_callbacks
+                    .extend(self.link_callback(&self.on_link_focus, target));
+            }
+        }
+        focused
+    }
+
+    fn on_event(&mut self, event: cursive_core::event::Event) -> cursive_core::event::EventResult {
+        use cursive_core::event::EventResult;
+
+        self.fragment_line = None;
+        let result = self.handle_event(event);
+        if !result.is_consumed() {
+            return result;
+        }
+        self.queue_focus_changed();
+        self.pending_callbacks
+            .drain(..)
+            .fold(EventResult::Consumed(None), |pending, callback| {
+                pending.and(EventResult::Consumed(Some(callback)))
+            })
+            .and(result)
+    }
+
+    fn important_area(&self, _: cursive_core::XY<usize>) -> cursive_core::Rect {
+        if let Some(doc) = &self.doc {
+            let area = match self.fragment_line {
+                Some(line) => cursive_core::Rect::from_size((0, line), (doc.size().x, 1)),
+                None => doc.link_handler.important_area(),
+            };
+            cursive_core::Rect::from_size(area.top_left() + self.padding, area.size())
+        } else {
+            cursive_core::Rect::from((0, 0))
+        }
+    }
+}
+
+impl<R: Renderer + 'static> LinkGutter<R> {
+    /// Creates a new link gutter for the given scroll view.
+    pub fn new(view: cursive_core::views::ScrollView<MarkupView<R>>) -> LinkGutter<R> {
+        LinkGutter {
+            view,
+            marker: '•',
+            style: theme::ColorStyle::secondary().into(),
+        }
+    }
+
+    /// Returns a reference to the wrapped scroll view.
+    pub fn get_inner(&self) -> &cursive_core::views::ScrollView<MarkupView<R>> {
+        &self.view
+    }
+
+    /// Returns a mutable reference to the wrapped scroll view.
+    pub fn get_inner_mut(&mut self) -> &mut cursive_core::views::ScrollView<MarkupView<R>> {
+        &mut self.view
+    }
+
+    /// Sets the character that marks the rows of the gutter that contain links.
+    ///
+    /// Per default, `•` is used.
+    pub fn set_marker(&mut self, marker: char) {
+        self.marker = marker;
+    }
+
+    /// Sets the style of the link markers.
+    ///
+    /// Per default, the secondary color of the palette is used.
+    pub fn set_style<S: Into<theme::Style>>(&mut self, style: S) {
+        self.style = style.into();
+    }
+
+    /// Returns the rows of the gutter that contain a link and the rows that correspond to the
+    /// visible part of the document, for a gutter with the given height.
+    fn rows(&self, height: usize) -> (Vec<bool>, ops::Range<usize>) {
+        let mut links = vec![false; height];
+        let markup = self.view.get_inner();
+        let total = std::cmp::max(self.view.inner_size().y, 1);
+        // If the document is shorter than the gutter, every row corresponds to one line.
+        let row = |y: usize| {
+            if total <= height {
+                y
+            } else {
+                y * height / total
+            }
+        };
+        if let Some(doc) = markup.document() {
+            for (_, position, _) in doc.links_visual_order() {
+                if let Some(link) = links.get_mut(row(position.y + markup.padding.y)) {
+                    *link = true;
+                }
+            }
+        }
+        let viewport = self.view.content_viewport();
+        let visible = if total <= height {
+            0..0
+        } else {
+            row(viewport.top())..std::cmp::min(row(viewport.bottom()) + 1, height)
+        };
+        (links, visible)
+    }
+}
+
+impl<R: Renderer + 'static> cursive_core::View for LinkGutter<R> {
+    fn draw(&self, printer: &cursive_core::Printer<'_, '_>) {
+        if printer.size.x == 0 {
+            return;
+        }
+        let width = printer.size.x - 1;
+        self.view.draw(&printer.cropped((width, printer.size.y)));
+
+        let (links, visible) = self.rows(printer.size.y);
+        let printer = printer.offset((width, 0));
+        for (y, link) in links.into_iter().enumerate() {
+            let text = if link {
+                self.marker.to_string()
+            } else {
+                " ".to_owned()
+            };
+            let style = if visible.contains(&y) {
+                self.style.combine(theme::ColorStyle::highlight_inactive())
+            } else {
+                self.style
+            };
+            printer.with_style(style, |printer| printer.print((0, y), &text));
+        }
+    }
+
+    fn layout(&mut self, size: cursive_core::XY<usize>) {
+        self.view.layout(size.saturating_sub((1, 0)));
+    }
+
+    fn needs_relayout(&self) -> bool {
+        self.view.needs_relayout()
+    }
+
+    fn required_size(&mut self, constraint: cursive_core::XY<usize>) -> cursive_core::XY<usize> {
+        self.view.required_size(constraint.saturating_sub((1, 0))) + (1, 0)
+    }
+
+    fn take_focus(&mut self, direction: cursive_core::direction::Direction) -> bool {
+        self.view.take_focus(direction)
+    }
+
+    fn on_event(&mut self, event: cursive_core::event::Event) -> cursive_core::event::EventResult {
+        self.view.on_event(event)
+    }
+
+    fn call_on_any<'a>(
+        &mut self,
+        selector: &cursive_core::view::Selector<'_>,
+        callback: cursive_core::event::AnyCb<'a>,
+    ) {
+        self.view.call_on_any(selector, callback)
+    }
+
+    fn focus_view(
+        &mut self,
+        selector: &cursive_core::view::Selector<'_>,
+    ) -> Result<(), cursive_core::view::ViewNotFound> {
+        self.view.focus_view(selector)
+    }
+
+    fn important_area(&self, size: cursive_core::XY<usize>) -> cursive_core::Rect {
+        self.view.important_area(size.saturating_sub((1, 0)))
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl TestRenderer {
+    /// Creates a new test renderer that produces the given lines.
+    pub fn new(lines: Vec<Vec<Element>>) -> TestRenderer {
+        TestRenderer { lines }
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl TestBackend {
+    /// Creates a new test backend with the given screen size.
+    pub fn new(size: cursive_core::XY<usize>) -> TestBackend {
+        TestBackend {
+            size,
+            cells: Default::default(),
+            color: std::cell::Cell::new(theme::ColorPair::from_256colors(0, 0)),
+        }
+    }
+
+    /// Returns a printer for the whole screen of this backend that uses the given theme.
+    pub fn printer<'a>(&'a self, theme: &'a theme::Theme) -> cursive_core::Printer<'a, 'a> {
+        cursive_core::Printer::new(self.size, theme, self)
+    }
+
+    /// Returns the printed text of the row with the given index, without trailing whitespace.
+    ///
+    /// Cells that have not been printed are treated as spaces.
+    pub fn row(&self, y: usize) -> String {
+        let cells = self.cells.borrow();
+        let row: String = (0..self.size.x)
+            .map(|x| cells.get(&(y, x)).map(|(c, _)| *c).unwrap_or(' '))
+            .collect();
+        row.trim_end().to_owned()
+    }
+
+    /// Returns the color of the cell with the given position, or `None` if it has not been
+    /// printed.
+    ///
+    /// The colors are resolved with the palette of the theme that is passed to [`printer`][], so
+    /// a view that is drawn again with another theme uses the colors of the new palette.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::theme::{Color, PaletteColor, Theme};
+    /// use cursive_core::View as _;
+    /// use cursive_markup::{Element, MarkupView, TestBackend, TestRenderer};
+    ///
+    /// let link = Element::link("link".to_owned(), Default::default(), "target".to_owned());
+    /// let mut view = MarkupView::with_renderer(TestRenderer::new(vec![vec![link]]));
+    /// view.layout((20, 1).into());
+    ///
+    /// let mut theme = Theme::default();
+    /// let backend = TestBackend::new((20, 1).into());
+    /// view.draw(&backend.printer(&theme));
+    /// let color = backend.color((0, 0).into()).unwrap();
+    /// assert_eq!(color.front, theme.palette[PaletteColor::Highlight]);
+    ///
+    /// // Change the palette and draw the view again without rendering the document again.
+    /// theme.palette[PaletteColor::Highlight] = Color::Rgb(0xff, 0x80, 0x00);
+    /// let backend = TestBackend::new((20, 1).into());
+    /// view.draw(&backend.printer(&theme));
+    /// let color = backend.color((0, 0).into()).unwrap();
+    /// assert_eq!(color.front, Color::Rgb(0xff, 0x80, 0x00));
+    /// ```
+    ///
+    /// [`printer`]: #method.printer
+    pub fn color(&self, position: cursive_core::XY<usize>) -> Option<theme::ColorPair> {
+        self.cells
+            .borrow()
+            .get(&(position.y, position.x))
+            .map(|(_, color)| *color)
+    }
+
+    /// Returns the indices of the rows that contain at least one printed cell.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::View as _;
+    /// use cursive_markup::{Element, MarkupView, TestBackend, TestRenderer};
+    ///
+    /// let lines = (0..10_000).map(|i| vec![Element::plain(format!("line {}", i))]).collect();
+    /// let mut view = MarkupView::with_renderer(TestRenderer::new(lines));
+    /// view.set_padding(1, 1);
+    /// view.set_hint(Some("hint".to_owned()));
+    /// let size = view.required_size((20, usize::MAX).into());
+    /// assert_eq!(size.y, 10_002);
+    /// view.layout(size);
+    ///
+    /// // Draw a scrolled window of five rows, like a scroll view does.
+    /// let theme = Default::default();
+    /// let backend = TestBackend::new((20, 5).into());
+    /// let printer = backend.printer(&theme).content_offset((0, 5000)).inner_size(size);
+    /// view.draw(&printer);
+    /// assert_eq!(backend.printed_rows(), vec![0, 1, 2, 3, 4]);
+    /// assert_eq!(backend.row(0), " line 4999");
+    /// assert_eq!(backend.row(4), " line 5003");
+    ///
+    /// let backend = TestBackend::new((20, 5).into());
+    /// let printer = backend.printer(&theme).content_offset((0, 9997)).inner_size(size);
+    /// view.draw(&printer);
+    /// assert_eq!(backend.row(3), " line 9999");
+    /// assert_eq!(backend.row(4), " hint");
+    /// ```
+    pub fn printed_rows(&self) -> Vec<usize> {
+        let mut rows: Vec<_> = self.cells.borrow().keys().map(|(y, _)| *y).collect();
+        rows.dedup();
+        rows
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl cursive_core::backend::Backend for TestBackend {
+    fn poll_event(&mut self) -> Option<cursive_core::event::Event> {
+        None
+    }
+
+    fn refresh(&mut self) {}
+
+    fn has_colors(&self) -> bool {
+        true
+    }
+
+    fn screen_size(&self) -> cursive_core::XY<usize> {
+        self.size
+    }
+
+    fn print_at(&self, pos: cursive_core::XY<usize>, text: &str) {
+        let mut cells = self.cells.borrow_mut();
+        let mut x = pos.x;
+        for c in text.chars() {
+            if x < self.size.x && pos.y < self.size.y {
+                cells.insert((pos.y, x), (c, self.color.get()));
+            }
+            x += c.width().unwrap_or_default();
+        }
+    }
+
+    fn clear(&self, _color: theme::Color) {
+        self.cells.borrow_mut().clear();
+    }
+
+    fn set_color(&self, colors: theme::ColorPair) -> theme::ColorPair {
+        self.color.replace(colors)
+    }
+
+    fn set_effect(&self, _effect: theme::Effect) {}
+
+    fn unset_effect(&self, _effect: theme::Effect) {}
+
+    fn name(&self) -> &str {
+        "test"
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl Renderer for TestRenderer {
+    fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
+        let mut doc = RenderedDocument::new(constraint);
+        for line in &self.lines {
+            doc.push_line(line.iter().cloned());
+        }
+        doc
+    }
+}
+
+/// Renders a document with the given renderer and width without a [`MarkupView`][].
+///
+/// This can be used to render a document outside of a `cursive` application, for example to
+/// export it.  The height of the constraint passed to the renderer is not limited.
+///
+/// [`MarkupView`]: struct.MarkupView.html
+pub fn render_to_document<R: Renderer + ?Sized>(renderer: &R, width: usize) -> RenderedDocument {
+    renderer.render((width, usize::MAX).into())
+}
+
+/// Returns a renderer for a document with the given content type and body, or `None` if the
+/// content type is not supported.
+///
+/// The content type is a MIME type like `text/html`.  Parameters like `charset` are ignored, so
+/// the body must already be decoded.  The following content types are supported:
+///
+/// - `text/html` and `application/xhtml+xml`:  rendered with a rich text [`html::Renderer`][]
+///   (requires the `html` feature, enabled per default)
+/// - `text/plain`:  rendered literally with a [`plain::Renderer`][], so escape sequences are not
+///   interpreted
+/// - `text/markdown`:  rendered as its source text with a [`plain::Renderer`][] as the crate does
+///   not provide a Markdown parser
+///
+/// # Example
+///
+/// ```
+/// let renderer = cursive_markup::renderer_for("text/plain", "\x1b[1mHello\x1b[0m");
+/// let doc = cursive_markup::render_to_document(renderer.as_deref().unwrap(), 80);
+/// assert_eq!(doc.to_plain_text(), "[1mHello[0m\n");
+///
+/// assert!(cursive_markup::renderer_for("image/png", "").is_none());
+/// ```
+///
+/// ```
+/// # #[cfg(feature = "html")] {
+/// let renderer = cursive_markup::renderer_for("text/html; charset=utf-8", "<p>Hello</p>");
+/// let doc = cursive_markup::render_to_document(renderer.as_deref().unwrap(), 80);
+/// assert_eq!(doc.to_plain_text(), "Hello\n");
+/// # }
+/// ```
+///
+/// [`html::Renderer`]: html/struct.Renderer.html
+/// [`plain::Renderer`]: plain/struct.Renderer.html
+pub fn renderer_for(content_type: &str, body: &str) -> Option<Box<dyn Renderer>> {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    match essence.as_str() {
+        #[cfg(feature = "html")]
+        "text/html" | "application/xhtml+xml" => Some(Box::new(html::Renderer::new(body))),
+        "text/plain" | "text/markdown" => Some(Box::new(plain::Renderer::new(body))),
+        _ => None,
+    }
+}
+
+/// Returns the OSC 8 escape sequence for a terminal hyperlink with the given target and text.
+///
+/// Terminals that support OSC 8 display the text as a clickable link to the target.  Note that
+/// `cursive` does not pass escape sequences to the terminal, so this string can only be used
+/// outside of a `cursive` view, for example when exporting a document.  Control characters in the
+/// target are percent-encoded so that they cannot terminate the escape sequence.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(
+///     cursive_markup::osc8_hyperlink("https://example.org/a\x1b\\b", "link"),
+///     "\x1b]8;;https://example.org/a%1B\\b\x1b\\link\x1b]8;;\x1b\\",
+/// );
+/// ```
+pub fn osc8_hyperlink(target: &str, text: &str) -> String {
+    let mut encoded = String::with_capacity(target.len());
+    for c in target.chars() {
+        if c.is_control() {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", encoded, text)
+}
+
+/// Converts the given link target into the Unicode normalization form C (NFC).
+///
+/// This can be used to compare link targets, for example to track visited links, as the same
+/// target can be encoded in different normalization forms.
+pub fn normalize_link_target(target: &str) -> String {
+    use unicode_normalization::UnicodeNormalization as _;
+    target.nfc().collect()
+}
+
+/// Replaces the control characters in the given text according to the given display mode.
+///
+/// Tabs, line feeds and carriage returns are kept.
+fn replace_control_chars(text: &str, display: ControlCharDisplay) -> std::borrow::Cow<'_, str> {
+    let is_replaced = |c: char| c.is_control() && !matches!(c, '\t' | '\n' | '\r');
+    if !text.contains(is_replaced) {
+        return text.into();
+    }
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !is_replaced(c) {
+            result.push(c);
+            continue;
+        }
+        let code = c as u32;
+        match display {
+            ControlCharDisplay::Caret => {
+                if code >= 0x80 {
+                    result.push_str("M-");
+                }
+                result.push('^');
+                result.push(char::from(((code & 0x7f) ^ 0x40) as u8));
+            }
+            ControlCharDisplay::Pictures if code < 0x20 => {
+                result.extend(std::char::from_u32(0x2400 + code));
+            }
+            ControlCharDisplay::Pictures if code == 0x7f => result.push('\u{2421}'),
+            ControlCharDisplay::Pictures => result.push('\u{fffd}'),
+            ControlCharDisplay::Strip => {}
+        }
+    }
+    result.into()
+}
+
+/// Returns the lowercase scheme of the given link target, `#` for in-page links or an empty
+/// string for other links without a scheme.
+fn link_scheme(target: &str) -> String {
+    if target.starts_with('#') {
+        return "#".to_owned();
+    }
+    let scheme = target
+        .find(':')
+        .map(|idx| &target[..idx])
+        .filter(|scheme| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        })
+        .unwrap_or_default();
+    scheme.to_lowercase()
+}
+
+/// Returns the SGR parameters for the given style.
+fn sgr_params(style: theme::Style, palette: &theme::Palette) -> Vec<String> {
+    let mut params: Vec<String> = style
+        .effects
+        .iter()
+        .filter_map(|effect| match effect {
+            theme::Effect::Simple => None,
+            theme::Effect::Bold => Some(1),
+            theme::Effect::Italic => Some(3),
+            theme::Effect::Underline => Some(4),
+            theme::Effect::Blink => Some(5),
+            theme::Effect::Reverse => Some(7),
+            theme::Effect::Strikethrough => Some(9),
+        })
+        .map(|param| param.to_string())
+        .collect();
+    for (color, offset) in &[(style.color.front, 0), (style.color.back, 10)] {
+        let color = match color {
+            theme::ColorType::Color(color) => *color,
+            theme::ColorType::Palette(color) => color.resolve(palette),
+            theme::ColorType::InheritParent => continue,
+        };
+        params.push(match color {
+            theme::Color::TerminalDefault => format!("{}", 39 + offset),
+            theme::Color::Dark(base) => format!("{}", 30 + offset + base as u8),
+            theme::Color::Light(base) => format!("{}", 90 + offset + base as u8),
+            theme::Color::Rgb(r, g, b) => format!("{};2;{};{};{}", 38 + offset, r, g, b),
+            theme::Color::RgbLowRes(r, g, b) => {
+                format!("{};5;{}", 38 + offset, 16 + 36 * r + 6 * g + b)
+            }
+        });
+    }
+    params
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::Decode(charset) => {
+                write!(f, "Failed to decode the document as {}", charset)
+            }
+            RenderError::UnknownCharset(charset) => write!(f, "Unknown charset: {}", charset),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl RenderedDocument {
+    /// Creates a new rendered document with the given size constraint.
+    ///
+    /// The size constraint is used to check whether a cached document can be reused or whether it
+    /// has to be rendered for the new constraint.  It is *not* enforced by this struct!
+    pub fn new(constraint: cursive_core::XY<usize>) -> RenderedDocument {
+        RenderedDocument {
+            lines: Vec::new(),
+            link_handler: Default::default(),
+            anchors: Vec::new(),
+            headings: Vec::new(),
+            sections: Vec::new(),
+            landmarks: Vec::new(),
+            line_styles: collections::HashMap::new(),
+            source_lines: collections::HashMap::new(),
+            max_links: None,
+            size: (0, 0).into(),
+            constraint,
+        }
+    }
+
+    /// Sets the maximum number of links in this document.
+    ///
+    /// If this limit is reached, [`push_line`][] adds the elements of further links as plain
+    /// styled text:  They remain visible, but they cannot be focused or selected.  The toggles of
+    /// collapsible sections count towards the limit, and sections whose toggle is added after the
+    /// limit has been reached keep their default state.  A link that is wrapped to the next line
+    /// only counts once.  Per default, the number of links is not limited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut toggle = Element::plain("▸ ".to_owned());
+    /// toggle.set_section_toggle(Some(0));
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.set_max_links(Some(1));
+    /// doc.push_line(vec![toggle]);
+    /// doc.push_line(vec![Element::link("a".to_owned(), Default::default(), "a".to_owned())]);
+    /// assert_eq!(doc.links_visual_order().count(), 0);
+    ///
+    /// let link = |text: &str, target: &str| {
+    ///     Element::link(text.to_owned(), Default::default(), target.to_owned())
+    /// };
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.set_max_links(Some(1));
+    /// doc.push_line(vec![Element::plain("x ".to_owned()), link("wrapped", "a")]);
+    /// doc.push_line(vec![link("link", "a"), Element::plain(" ".to_owned()), link("b", "b")]);
+    /// assert_eq!(doc.links_visual_order().count(), 2);
+    /// ```
+    ///
+    /// [`push_line`]: #method.push_line
+    pub fn set_max_links(&mut self, max_links: Option<usize>) {
+        self.max_links = max_links;
+    }
+
+    /// Appends a rendered line to the document.
+    ///
+    /// The width of the elements is determined with [`unicode-width`][], so wide characters, for
+    /// example CJK characters, take up two columns.  The same width is used for the size of the
+    /// document, the positions of the links and for drawing the elements.
+    ///
+    /// Consecutive elements in the same line with the same link target, for example the parts of
+    /// a link with different styles, are added as one link.  If the text of an element contains
+    /// newlines, it is split into multiple lines.  If the element has a link target, every part of
+    /// the text is added as a separate link.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.push_line(vec![
+    ///     Element::plain("日本a".to_owned()),
+    ///     Element::link("語".to_owned(), Default::default(), "target".to_owned()),
+    /// ]);
+    /// assert_eq!(doc.size(), (7, 1).into());
+    /// assert_eq!(doc.link_at((4, 0).into()), None);
+    /// assert_eq!(doc.link_at((5, 0).into()), Some(0));
+    /// assert_eq!(doc.link_at((6, 0).into()), Some(0));
+    ///
+    /// doc.push_line(vec![Element::link("a\nbc".to_owned(), Default::default(), "t".to_owned())]);
+    /// assert_eq!(doc.size(), (7, 3).into());
+    /// assert_eq!(doc.link_at((0, 1).into()), Some(1));
+    /// assert_eq!(doc.link_at((1, 2).into()), Some(2));
+    ///
+    /// let bold = cursive_core::theme::Effect::Bold.into();
+    /// doc.push_line(vec![
+    ///     Element::link("bold".to_owned(), bold, "u".to_owned()),
+    ///     Element::link(" plain".to_owned(), Default::default(), "u".to_owned()),
+    /// ]);
+    /// assert_eq!(doc.link_at((0, 3).into()), Some(3));
+    /// assert_eq!(doc.link_at((9, 3).into()), Some(3));
+    /// ```
+    ///
+    /// [`unicode-width`]: https://docs.rs/unicode-width
+    pub fn push_line<I: IntoIterator<Item = Element>>(&mut self, line: I) {
+        let mut rendered_line = Vec::new();
+        let mut x = 0;
+        // The link of the previous element in the current line, if any.
+        let mut last_link: Option<usize> = None;
         for element in line {
-            let width = element.text.width();
-            let link_idx = element.link_target.map(|target| {
-                self.link_handler.push(Link {
-                    position: (x, y).into(),
+            let Element {
+                text,
+                style,
+                link_target,
+                link_disabled,
+                link_description,
+                access_key,
+                section_toggle,
+                embed_id,
+            } = element;
+            for (i, text) in text.split('\n').enumerate() {
+                if i > 0 {
+                    self.finish_line(std::mem::take(&mut rendered_line), x);
+                    x = 0;
+                    last_link = None;
+                }
+                if text.is_empty() {
+                    continue;
+                }
+                let width = text.width();
+                let continued = last_link.filter(|&idx| {
+                    let link = &self.link_handler.links[idx];
+                    section_toggle.is_none()
+                        && link.section.is_none()
+                        && !link_disabled
+                        && link_target.as_ref() == Some(&link.target)
+                        && link.description == link_description
+                        && link.access_key == access_key
+                });
+                let max_links = self.max_links.unwrap_or(usize::MAX);
+                let link_idx = if let Some(idx) = continued {
+                    self.link_handler.links[idx].width += width;
+                    Some(idx)
+                } else {
+                    let link_target = match section_toggle {
+                        Some(_) => Some(String::new()),
+                        None => link_target.clone().filter(|_| !link_disabled),
+                    };
+                    let link = link_target.map(|target| Link {
+                        position: (x, self.lines.len()).into(),
+                        width,
+                        target,
+                        description: link_description.clone(),
+                        access_key,
+                        section: section_toggle,
+                    });
+                    // The parts of a wrapped link only count as one link.
+                    let handler = &mut self.link_handler;
+                    link.filter(|link| handler.link_count < max_links || handler.is_next_part(link))
+                        .map(|link| handler.push(link))
+                };
+                last_link = link_idx;
+                x += width;
+                rendered_line.push(RenderedElement {
+                    text: text.to_owned(),
                     width,
-                    target,
-                })
-            });
-            x += width;
-            rendered_line.push(RenderedElement {
-                text: element.text,
-                style: element.style,
-                link_idx,
-            });
+                    style,
+                    link_idx,
+                    embed_id: embed_id.clone(),
+                });
+            }
+        }
+        self.finish_line(rendered_line, x);
+    }
+
+    /// Appends the given elements to the document, wrapping them into lines with the given
+    /// maximum width.
+    ///
+    /// In contrast to [`push_line`][], the elements don't have to be wrapped already.  The lines
+    /// are broken at whitespace, and words that are wider than the maximum width are broken at
+    /// the last character that fits into the line.  The styles and link targets of the elements
+    /// are preserved, and the whitespace at the wrap points is removed.  Newlines in the text of
+    /// an element start a new line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((10, 10).into());
+    /// doc.push_wrapped(
+    ///     vec![
+    ///         Element::plain("Read the ".to_owned()),
+    ///         Element::link("Rust book".to_owned(), Default::default(), "book".to_owned()),
+    ///         Element::plain(" today".to_owned()),
+    ///     ],
+    ///     10,
+    /// );
+    /// assert_eq!(doc.size(), (9, 3).into());
+    /// assert_eq!(doc.link_at((0, 1).into()), Some(0));
+    /// assert_eq!(doc.link_at((8, 1).into()), Some(0));
+    /// ```
+    ///
+    /// [`push_line`]: #method.push_line
+    pub fn push_wrapped<I: IntoIterator<Item = Element>>(&mut self, spans: I, width: usize) {
+        let mut wrapper = LineWrapper::new(width);
+        for (idx, mut element) in spans.into_iter().enumerate() {
+            let text = std::mem::take(&mut element.text);
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    wrapper.flush_word();
+                    wrapper.finish_line();
+                }
+                wrapper.push(
+                    idx,
+                    Element {
+                        text: line.to_owned(),
+                        ..element.clone()
+                    },
+                );
+            }
+        }
+        for line in wrapper.finish() {
+            self.push_line(line);
+        }
+    }
+
+    /// Inserts the given markers before and after every run of elements with the same link
+    /// target and updates the link positions and the document size.
+    fn add_link_markers(&mut self, start: &str, end: &str) {
+        let links = &self.link_handler.links;
+        let target = |element: Option<&RenderedElement>| {
+            element
+                .and_then(|element| element.link_idx)
+                .filter(|&link_idx| links[link_idx].section.is_none())
+                .map(|link_idx| links[link_idx].target.clone())
+        };
+        for line in &mut self.lines {
+            let mut marked_line = Vec::new();
+            for (i, element) in line.iter().enumerate() {
+                let current = target(Some(element));
+                let marker = |text: &str| RenderedElement {
+                    text: text.to_owned(),
+                    width: text.width(),
+                    style: element.style,
+                    link_idx: None,
+                    embed_id: None,
+                };
+                if current.is_some() && (i == 0 || target(line.get(i - 1)) != current) {
+                    marked_line.push(marker(start));
+                }
+                marked_line.push(element.clone());
+                if current.is_some() && target(line.get(i + 1)) != current {
+                    marked_line.push(marker(end));
+                }
+            }
+            *line = marked_line;
+        }
+
+        let mut size = cursive_core::XY::new(0, 0);
+        for line in &self.lines {
+            let mut x = 0;
+            for element in line {
+                if let Some(link_idx) = element.link_idx {
+                    self.link_handler.links[link_idx].position.x = x;
+                }
+                x += element.width;
+            }
+            size = size.stack_vertical(&(x, 1).into());
+        }
+        self.size = size;
+    }
+
+    /// Removes the body lines of the closed sections and updates the markers of the section
+    /// toggles.
+    ///
+    /// The given function receives the index of a section and whether it is open per default and
+    /// returns whether it should be displayed as open.  Anchors and headings in removed lines
+    /// point to the next visible line.
+    fn hide_sections<F: Fn(usize, bool) -> bool>(&mut self, is_open: F) {
+        let mut hidden = vec![false; self.lines.len()];
+        let mut open_sections = Vec::new();
+        for (idx, (body, open)) in self.sections.iter().enumerate() {
+            let open = is_open(idx, *open);
+            if !open {
+                for line in hidden.iter_mut().take(body.end).skip(body.start) {
+                    *line = true;
+                }
+            }
+            open_sections.push(open);
+        }
+
+        let old_links = std::mem::take(&mut self.link_handler).links;
+        let mut link_map = vec![None; old_links.len()];
+        let mut line_map = Vec::new();
+        let mut size = cursive_core::XY::new(0, 0);
+        for (y, mut line) in std::mem::take(&mut self.lines).into_iter().enumerate() {
+            line_map.push(self.lines.len());
+            if hidden[y] {
+                continue;
+            }
+            let mut x = 0;
+            for element in &mut line {
+                if let Some(link_idx) = element.link_idx {
+                    let link = &old_links[link_idx];
+                    let is_marker = element.text == SECTION_OPEN_MARKER
+                        || element.text == SECTION_CLOSED_MARKER;
+                    if let Some(section) = link.section.filter(|_| is_marker) {
+                        element.text = if open_sections.get(section).copied().unwrap_or(true) {
+                            SECTION_OPEN_MARKER.to_owned()
+                        } else {
+                            SECTION_CLOSED_MARKER.to_owned()
+                        };
+                        element.width = element.text.width();
+                    }
+                    let new_idx = *link_map[link_idx].get_or_insert_with(|| {
+                        self.link_handler.push(Link {
+                            position: (x, self.lines.len()).into(),
+                            ..link.clone()
+                        })
+                    });
+                    element.link_idx = Some(new_idx);
+                }
+                x += element.width;
+            }
+            self.lines.push(line);
+            size = size.stack_vertical(&(x, 1).into());
+        }
+        line_map.push(self.lines.len());
+        self.size = size;
+
+        let map = |line: usize| line_map[std::cmp::min(line, line_map.len() - 1)];
+        for (_, line) in &mut self.anchors {
+            *line = map(*line);
+        }
+        for (_, _, line) in &mut self.headings {
+            *line = map(*line);
+        }
+        for (body, _) in &mut self.sections {
+            *body = map(body.start)..map(body.end);
+        }
+        for (_, lines) in &mut self.landmarks {
+            *lines = map(lines.start)..map(lines.end);
+        }
+        self.line_styles = std::mem::take(&mut self.line_styles)
+            .into_iter()
+            .filter(|(line, _)| !hidden.get(*line).copied().unwrap_or_default())
+            .map(|(line, style)| (map(line), style))
+            .collect();
+        self.source_lines = std::mem::take(&mut self.source_lines)
+            .into_iter()
+            .filter(|(line, _)| !hidden.get(*line).copied().unwrap_or_default())
+            .map(|(line, source_line)| (map(line), source_line))
+            .collect();
+    }
+
+    /// Distributes the lines to the given number of columns with the same height.
+    ///
+    /// The columns start at multiples of the given offset.  Anchors, headings and the other
+    /// line references point to the row of the line, and the line styles and source lines are
+    /// removed.
+    fn arrange_columns(&mut self, columns: usize, offset: usize) {
+        let mut height = self.lines.len() / columns;
+        if height * columns < self.lines.len() {
+            height += 1;
+        }
+        if height == 0 {
+            return;
+        }
+
+        let line_count = self.lines.len();
+        let old_links = std::mem::take(&mut self.link_handler).links;
+        let mut link_map = vec![None; old_links.len()];
+        let mut rows = vec![Vec::new(); height];
+        let mut widths = vec![0; height];
+        for (y, line) in std::mem::take(&mut self.lines).into_iter().enumerate() {
+            let row = y % height;
+            let origin = y / height * offset;
+            if widths[row] < origin {
+                let width = origin - widths[row];
+                rows[row].push(RenderedElement {
+                    text: " ".repeat(width),
+                    width,
+                    ..Default::default()
+                });
+                widths[row] = origin;
+            }
+            for mut element in line {
+                if let Some(link_idx) = element.link_idx {
+                    let link = &old_links[link_idx];
+                    let x = widths[row];
+                    let new_idx = *link_map[link_idx].get_or_insert_with(|| {
+                        self.link_handler.push(Link {
+                            position: (x, row).into(),
+                            ..link.clone()
+                        })
+                    });
+                    element.link_idx = Some(new_idx);
+                }
+                widths[row] += element.width;
+                rows[row].push(element);
+            }
+        }
+        self.size = (widths.iter().copied().max().unwrap_or_default(), height).into();
+        self.lines = rows;
+
+        let map = |line: usize| {
+            if line < line_count {
+                line % height
+            } else {
+                height
+            }
+        };
+        // The end of a range is exclusive, so we map the last line of the range instead.  A range
+        // that continues in the next column covers rows at the bottom and at the top of the
+        // columns, so we use all rows for it.
+        let map_range = |lines: &ops::Range<usize>| {
+            if lines.end <= lines.start {
+                map(lines.start)..map(lines.start)
+            } else if lines.start / height != (lines.end - 1) / height {
+                0..height
+            } else {
+                map(lines.start)..map(lines.end - 1) + 1
+            }
+        };
+        for (_, line) in &mut self.anchors {
+            *line = map(*line);
+        }
+        for (_, _, line) in &mut self.headings {
+            *line = map(*line);
+        }
+        for (body, _) in &mut self.sections {
+            *body = map_range(body);
+        }
+        for (_, lines) in &mut self.landmarks {
+            *lines = map_range(lines);
+        }
+        self.line_styles.clear();
+        self.source_lines.clear();
+    }
+
+    fn finish_line(&mut self, line: Vec<RenderedElement>, width: usize) {
+        self.lines.push(line);
+        self.size = self.size.stack_vertical(&(width, 1).into());
+    }
+
+    /// Adds an anchor with the given name that points to the next line of the document.
+    ///
+    /// Anchors are the targets of in-page links, for example the `id` attributes of HTML
+    /// elements.  They can be looked up with [`anchor`][].
+    ///
+    /// [`anchor`]: #method.anchor
+    pub fn push_anchor(&mut self, name: String) {
+        self.anchors.push((name, self.lines.len()));
+    }
+
+    /// Sets the base style of the line with the given index.
+    ///
+    /// The styles of the elements in the line are combined with this style, and the remaining
+    /// width of the line is filled with it, for example to use alternating background colors for
+    /// the rows of a table.  Per default, lines don't have a base style.
+    pub fn set_line_style(&mut self, line: usize, style: theme::Style) {
+        self.line_styles.insert(line, style);
+    }
+
+    /// Sets the line of the source document that the line with the given index originates from.
+    ///
+    /// The source line is the zero-based index of a line in the text that was passed to the
+    /// renderer.  It can be queried with [`source_line`][], for example to map a row of the view
+    /// back to the source for error messages.
+    ///
+    /// [`source_line`]: #method.source_line
+    pub fn set_source_line(&mut self, line: usize, source_line: usize) {
+        self.source_lines.insert(line, source_line);
+    }
+
+    /// Returns the line of the source document that the line with the given index originates
+    /// from, if known.
+    ///
+    /// The [`ansi::Renderer`][] sets the source line for all lines.  The HTML renderer does not
+    /// set source lines as `html2text` does not keep the positions of the source text.  If the
+    /// document is arranged in [multiple columns][`MarkupView::set_columns`], the source lines are
+    /// not available.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.push_line(vec![Element::plain("title".to_owned())]);
+    /// doc.set_source_line(0, 2);
+    /// assert_eq!(doc.source_line(0), Some(2));
+    /// assert_eq!(doc.source_line(1), None);
+    /// ```
+    ///
+    /// [`ansi::Renderer`]: ansi/struct.Renderer.html
+    /// [`MarkupView::set_columns`]: struct.MarkupView.html#method.set_columns
+    pub fn source_line(&self, line: usize) -> Option<usize> {
+        self.source_lines.get(&line).copied()
+    }
+
+    /// Adds a collapsible section with the given body lines.
+    ///
+    /// The body of a closed section is hidden by [`MarkupView`][].  The section can be opened and
+    /// closed by selecting an element that has been marked as its toggle with
+    /// [`Element::set_section_toggle`][].  The sections are numbered in the order in which they
+    /// are added, starting at zero.
+    ///
+    /// [`MarkupView`]: struct.MarkupView.html
+    /// [`Element::set_section_toggle`]: struct.Element.html#method.set_section_toggle
+    pub fn push_section(&mut self, body: ops::Range<usize>, open: bool) {
+        self.sections.push((body, open));
+    }
+
+    /// Adds a landmark of the given kind that spans the given lines.
+    ///
+    /// The landmarks can be used to skip regions of the document, for example to jump past the
+    /// navigation links to the main content, see [`landmarks`][].
+    ///
+    /// [`landmarks`]: #method.landmarks
+    pub fn push_landmark(&mut self, kind: LandmarkKind, lines: ops::Range<usize>) {
+        self.landmarks.push((kind, lines));
+    }
+
+    /// Returns the kind and the lines of all landmarks in this document.
+    ///
+    /// The landmarks are sorted by the order of their start in the source document, so an outer
+    /// landmark precedes the landmarks that are nested in it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, LandmarkKind, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.push_line(vec![Element::plain("Home".to_owned())]);
+    /// doc.push_line(vec![Element::plain("Text".to_owned())]);
+    /// doc.push_landmark(LandmarkKind::Main, 0..2);
+    /// doc.push_landmark(LandmarkKind::Navigation, 0..1);
+    /// assert_eq!(
+    ///     doc.landmarks(),
+    ///     &[(LandmarkKind::Main, 0..2), (LandmarkKind::Navigation, 0..1)],
+    /// );
+    ///
+    /// let html = "<header>Title</header><nav><a href='a'>A</a></nav><main><p>Text</p></main>";
+    /// let doc = cursive_markup::html::render_to_document(html, 80);
+    /// let kinds: std::collections::HashSet<_> = doc.landmarks().iter().map(|(k, _)| *k).collect();
+    /// assert_eq!(kinds.len(), 3);
+    /// assert!(kinds.contains(&LandmarkKind::Navigation));
+    /// ```
+    pub fn landmarks(&self) -> &[(LandmarkKind, ops::Range<usize>)] {
+        &self.landmarks
+    }
+
+    /// Adds a heading with the given level and text that starts in the next line of the document.
+    ///
+    /// The headings are used to generate a table of contents, see [`headings`][].
+    ///
+    /// [`headings`]: #method.headings
+    pub fn push_heading(&mut self, level: u8, text: String) {
+        self.headings.push((level, text, self.lines.len()));
+    }
+
+    /// Returns the level, the text and the line of all headings in this document.
+    pub fn headings(&self) -> &[(u8, String, usize)] {
+        &self.headings
+    }
+
+    /// Returns the line of the anchor with the given name, if any.
+    ///
+    /// If there are multiple anchors with the same name, the first one is used.
+    pub fn anchor(&self, name: &str) -> Option<usize> {
+        self.anchors
+            .iter()
+            .find(|(anchor, _)| anchor == name)
+            .map(|(_, line)| *line)
+    }
+
+    /// Appends the lines and links of the given document to this document.
+    ///
+    /// The positions and indices of the links and anchors of the appended document are adjusted
+    /// accordingly.  The constraint of this document is not changed.  The link limit of this
+    /// document also applies to the appended links, see [`set_max_links`][].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let link = |t: &str| Element::link(t.to_owned(), Default::default(), t.to_owned());
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.set_max_links(Some(2));
+    /// doc.push_line(vec![link("a")]);
+    /// let mut other = RenderedDocument::new((80, 10).into());
+    /// other.push_line(vec![link("b"), Element::plain(" ".to_owned()), link("c")]);
+    /// doc.extend(other);
+    /// assert_eq!(doc.links_visual_order().count(), 2);
+    /// assert_eq!(doc.to_plain_text(), "a\nb c\n");
+    /// ```
+    ///
+    /// [`set_max_links`]: #method.set_max_links
+    pub fn extend(&mut self, other: RenderedDocument) {
+        let y_offset = self.lines.len();
+        self.anchors.extend(
+            other
+                .anchors
+                .into_iter()
+                .map(|(name, line)| (name, line + y_offset)),
+        );
+        self.headings.extend(
+            other
+                .headings
+                .into_iter()
+                .map(|(level, text, line)| (level, text, line + y_offset)),
+        );
+        self.line_styles.extend(
+            other
+                .line_styles
+                .into_iter()
+                .map(|(line, style)| (line + y_offset, style)),
+        );
+        self.source_lines.extend(
+            other
+                .source_lines
+                .into_iter()
+                .map(|(line, source_line)| (line + y_offset, source_line)),
+        );
+        self.landmarks.extend(
+            other
+                .landmarks
+                .into_iter()
+                .map(|(kind, lines)| (kind, (lines.start + y_offset)..(lines.end + y_offset))),
+        );
+        let section_offset = self.sections.len();
+        self.sections.extend(
+            other
+                .sections
+                .into_iter()
+                .map(|(body, open)| ((body.start + y_offset)..(body.end + y_offset), open)),
+        );
+        // Links that exceed the limit of this document are kept as plain text, see
+        // `set_max_links`.
+        let max_links = self.max_links.unwrap_or(usize::MAX);
+        let mut link_map = Vec::with_capacity(other.link_handler.links.len());
+        for mut link in other.link_handler.links {
+            link.position.y += y_offset;
+            if let Some(section) = &mut link.section {
+                *section += section_offset;
+            }
+            let handler = &mut self.link_handler;
+            if handler.link_count < max_links || handler.is_next_part(&link) {
+                link_map.push(Some(handler.push(link)));
+            } else {
+                link_map.push(None);
+            }
+        }
+        for mut line in other.lines {
+            for element in &mut line {
+                element.link_idx = element.link_idx.and_then(|idx| link_map[idx]);
+            }
+            self.lines.push(line);
+        }
+        self.size = self.size.stack_vertical(&other.size);
+    }
+
+    /// Returns the size of this document.
+    ///
+    /// The width is the width of the longest line, and the height is the number of lines.
+    pub fn size(&self) -> cursive_core::XY<usize> {
+        self.size
+    }
+
+    /// Converts this document into a styled string, for example to display it in a `TextView`.
+    ///
+    /// The lines are separated by newlines.  Links keep their style, but they cannot be focused
+    /// or selected.
+    pub fn to_styled_string(&self) -> cursive_core::utils::markup::StyledString {
+        let mut s = cursive_core::utils::markup::StyledString::new();
+        for (y, line) in self.lines.iter().enumerate() {
+            if y > 0 {
+                s.append_plain("\n");
+            }
+            for element in line {
+                s.append_styled(&element.text, element.style);
+            }
+        }
+        s
+    }
+
+    /// Converts this document into a string with ANSI escape sequences, for example to save it to
+    /// a file.
+    ///
+    /// The text effects and colors of the elements are converted into SGR sequences, and links
+    /// are converted into terminal hyperlinks (see [`osc8_hyperlink`][]).  The toggles of
+    /// collapsible sections and links with an empty target are not converted into hyperlinks.
+    /// Palette colors are resolved with the default palette of `cursive`, see
+    /// [`to_ansi_with_palette`][] for using the palette of a custom theme.  See
+    /// [`to_plain_text`][] for a conversion without escape sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// let mut toggle = Element::plain("Details".to_owned());
+    /// toggle.set_section_toggle(Some(0));
+    /// doc.push_line(vec![toggle]);
+    /// let target = "https://rust-lang.org".to_owned();
+    /// doc.push_line(vec![Element::link("Rust".to_owned(), Default::default(), target)]);
+    /// doc.push_section(1..2, true);
+    /// assert_eq!(
+    ///     doc.to_ansi(),
+    ///     "Details\n\x1b]8;;https://rust-lang.org\x1b\\Rust\x1b]8;;\x1b\\\n",
+    /// );
+    /// ```
+    ///
+    /// [`osc8_hyperlink`]: fn.osc8_hyperlink.html
+    /// [`to_ansi_with_palette`]: #method.to_ansi_with_palette
+    /// [`to_plain_text`]: #method.to_plain_text
+    pub fn to_ansi(&self) -> String {
+        self.to_ansi_with_palette(&theme::Palette::default())
+    }
+
+    /// Converts this document into a string with ANSI escape sequences, resolving palette colors
+    /// with the given palette.
+    ///
+    /// See [`to_ansi`][] for more information.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::theme::{BaseColor, Color, Palette, PaletteColor, Style};
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// let style = Style::from(PaletteColor::Highlight);
+    /// doc.push_line(vec![Element::styled("a".to_owned(), style)]);
+    ///
+    /// let mut palette = Palette::default();
+    /// palette[PaletteColor::Highlight] = Color::Dark(BaseColor::Red);
+    /// assert_eq!(doc.to_ansi_with_palette(&palette), "\x1b[31ma\x1b[0m\n");
+    /// ```
+    ///
+    /// [`to_ansi`]: #method.to_ansi
+    pub fn to_ansi_with_palette(&self, palette: &theme::Palette) -> String {
+        let mut s = String::new();
+        for line in &self.lines {
+            for element in line {
+                let params = sgr_params(element.style, palette);
+                let text = if params.is_empty() {
+                    element.text.clone()
+                } else {
+                    format!("\x1b[{}m{}\x1b[0m", params.join(";"), element.text)
+                };
+                let target = element
+                    .link_idx
+                    .map(|link_idx| &self.link_handler.links[link_idx])
+                    .filter(|link| link.section.is_none() && !link.target.is_empty())
+                    .map(|link| &link.target);
+                match target {
+                    Some(target) => s.push_str(&osc8_hyperlink(target, &text)),
+                    None => s.push_str(&text),
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Converts this document into a string without styles and links.
+    pub fn to_plain_text(&self) -> String {
+        let mut s = String::new();
+        for line in &self.lines {
+            for element in line {
+                s.push_str(&element.text);
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    /// Returns the index of the link at the given position, if any.
+    ///
+    /// A link matches if it is in the same line as the position and if the position is within the
+    /// columns covered by the link.  The toggles of collapsible sections are not links, so they
+    /// are not taken into account for the indices.
+    pub fn link_at(&self, pos: cursive_core::XY<usize>) -> Option<usize> {
+        let link_idx = self.link_handler.links.iter().position(|link| {
+            link.position.y == pos.y
+                && link.position.x <= pos.x
+                && pos.x < link.position.x + link.width
+        })?;
+        self.public_link_index(link_idx)
+    }
+
+    /// Returns the indices of the lines that differ between this document and the given document.
+    ///
+    /// Two lines are equal if they consist of the same elements with the same text, style and
+    /// link target.  If one document has more lines than the other, the additional lines are also
+    /// reported as changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let old = cursive_markup::html::render_to_document("<p>a</p><p>b</p>", 80);
+    /// let new = cursive_markup::html::render_to_document("<p>a</p><p>c</p>", 80);
+    /// assert_eq!(old.diff(&new), vec![2]);
+    /// ```
+    pub fn diff(&self, other: &RenderedDocument) -> Vec<usize> {
+        let element_eq = |a: &RenderedElement, b: &RenderedElement| {
+            let target = |doc: &RenderedDocument, element: &RenderedElement| {
+                element
+                    .link_idx
+                    .map(|link_idx| doc.link_handler.links[link_idx].target.clone())
+            };
+            a.text == b.text && a.style == b.style && target(self, a) == target(other, b)
+        };
+        let line_count = std::cmp::max(self.lines.len(), other.lines.len());
+        (0..line_count)
+            .filter(|&y| match (self.lines.get(y), other.lines.get(y)) {
+                (Some(a), Some(b)) => {
+                    a.len() != b.len() || a.iter().zip(b).any(|(a, b)| !element_eq(a, b))
+                }
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Returns the ID, the position and the width of all placeholders for embedded objects.
+    ///
+    /// Placeholders are elements with an embed ID, see [`Element::set_embed_id`][].  If the
+    /// placeholder spans multiple lines, every line is returned separately.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut placeholder = Element::plain("[video]".to_owned());
+    /// placeholder.set_embed_id(Some("video-1".to_owned()));
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.push_line(vec![Element::plain("Watch: ".to_owned()), placeholder]);
+    /// assert_eq!(doc.embeds(), vec![("video-1", (7, 0).into(), 7)]);
+    /// ```
+    ///
+    /// [`Element::set_embed_id`]: struct.Element.html#method.set_embed_id
+    pub fn embeds(&self) -> Vec<(&str, cursive_core::XY<usize>, usize)> {
+        let mut embeds = Vec::new();
+        for (y, line) in self.lines.iter().enumerate() {
+            let mut x = 0;
+            for element in line {
+                if let Some(embed_id) = &element.embed_id {
+                    embeds.push((embed_id.as_str(), (x, y).into(), element.width));
+                }
+                x += element.width;
+            }
+        }
+        embeds
+    }
+
+    /// Returns the index, the position and the target of all links in visual order.
+    ///
+    /// The links are sorted by their position, from top to bottom and from left to right, see
+    /// [`NavigationOrder::VisualOrder`][].  The index is the index of the link in document order,
+    /// as returned by [`link_at`][].  The toggles of collapsible sections are not included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.push_line(vec![
+    ///     Element::plain("text ".to_owned()),
+    ///     Element::link("a".to_owned(), Default::default(), "a".to_owned()),
+    /// ]);
+    /// doc.push_line(vec![Element::link("b".to_owned(), Default::default(), "b".to_owned())]);
+    /// let links: Vec<_> = doc.links_visual_order().collect();
+    /// assert_eq!(links, vec![(0, (5, 0).into(), "a"), (1, (0, 1).into(), "b")]);
+    /// ```
+    ///
+    /// [`NavigationOrder::VisualOrder`]: enum.NavigationOrder.html#variant.VisualOrder
+    /// [`link_at`]: #method.link_at
+    pub fn links_visual_order(
+        &self,
+    ) -> impl Iterator<Item = (usize, cursive_core::XY<usize>, &str)> + '_ {
+        let links = &self.link_handler.links;
+        let indices = self.public_link_indices();
+        self.link_handler
+            .visual_order()
+            .into_iter()
+            .filter_map(move |idx| {
+                indices[idx]
+                    .map(|public_idx| (public_idx, links[idx].position, links[idx].target.as_str()))
+            })
+    }
+
+    /// Returns the index of the given link without counting the section toggles, or `None` if it
+    /// is a section toggle.
+    fn public_link_index(&self, link_idx: usize) -> Option<usize> {
+        let links = &self.link_handler.links;
+        if links[link_idx].section.is_some() {
+            None
+        } else {
+            Some(
+                links[..link_idx]
+                    .iter()
+                    .filter(|link| link.section.is_none())
+                    .count(),
+            )
+        }
+    }
+
+    /// Returns the index without counting the section toggles for every link, see
+    /// `public_link_index`.
+    fn public_link_indices(&self) -> Vec<Option<usize>> {
+        let mut count = 0;
+        self.link_handler
+            .links
+            .iter()
+            .map(|link| {
+                if link.section.is_some() {
+                    None
+                } else {
+                    count += 1;
+                    Some(count - 1)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the position and width of the first occurrence of the query after the given
+    /// position.
+    fn find(
+        &self,
+        query: &str,
+        after: Option<cursive_core::XY<usize>>,
+    ) -> Option<(cursive_core::XY<usize>, usize)> {
+        if query.is_empty() {
+            return None;
+        }
+        let start_y = after.map(|pos| pos.y).unwrap_or_default();
+        for (y, line) in self.lines.iter().enumerate().skip(start_y) {
+            let text: String = line.iter().map(|element| element.text.as_str()).collect();
+            for (idx, _) in text.match_indices(query) {
+                let x = text[..idx].width();
+                if after.map(|pos| (y, x) > (pos.y, pos.x)).unwrap_or(true) {
+                    return Some(((x, y).into(), query.width()));
+                }
+            }
         }
-        self.lines.push(rendered_line);
-        self.size = self.size.stack_vertical(&(x, 1).into());
+        None
+    }
+
+    fn link_text(&self, link_idx: usize) -> String {
+        self.lines
+            .iter()
+            .flatten()
+            .filter(|element| element.link_idx == Some(link_idx))
+            .map(|element| element.text.as_str())
+            .collect()
     }
 }
 
@@ -373,6 +3944,7 @@ impl Element {
             text,
             style,
             link_target,
+            ..Default::default()
         }
     }
 
@@ -393,6 +3965,104 @@ impl Element {
     pub fn link(text: String, style: theme::Style, target: String) -> Element {
         Element::new(text, style, Some(target))
     }
+
+    /// Creates a disabled link with the given text, style and link target.
+    ///
+    /// A disabled link is drawn with its style like a normal link, but it cannot be focused or
+    /// selected and it is not part of the links of the rendered document.  This can be used for
+    /// links that should be shown but not be navigable, for example the current page in a
+    /// breadcrumb.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::{Element, RenderedDocument};
+    ///
+    /// let mut doc = RenderedDocument::new((80, 10).into());
+    /// doc.push_line(vec![
+    ///     Element::link("Home".to_owned(), Default::default(), "/".to_owned()),
+    ///     Element::plain(" > ".to_owned()),
+    ///     Element::disabled_link("Docs".to_owned(), Default::default(), "/docs".to_owned()),
+    /// ]);
+    /// let links: Vec<_> = doc.links_visual_order().collect();
+    /// assert_eq!(links, vec![(0, (0, 0).into(), "/")]);
+    /// ```
+    pub fn disabled_link(text: String, style: theme::Style, target: String) -> Element {
+        Element {
+            link_disabled: true,
+            ..Element::link(text, style, target)
+        }
+    }
+
+    /// Sets the description of the link of this element, for example its `aria-label`.
+    ///
+    /// See [`MarkupView::on_link_description`][].  The description is ignored if this element
+    /// does not have a link target.
+    ///
+    /// [`MarkupView::on_link_description`]: struct.MarkupView.html#method.on_link_description
+    pub fn set_link_description(&mut self, description: Option<String>) {
+        self.link_description = description;
+    }
+
+    /// Sets the access key of the link of this element.
+    ///
+    /// If the access key is pressed together with the Alt key, the link is focused and selected.
+    /// The access key is ignored if this element does not have a link target.
+    pub fn set_access_key(&mut self, access_key: Option<char>) {
+        self.access_key = access_key;
+    }
+
+    /// Marks this element as a placeholder for an embedded object with the given ID.
+    ///
+    /// The position of the placeholder in the rendered document can be queried with
+    /// [`RenderedDocument::embeds`][], for example to draw another view on top of it.
+    ///
+    /// [`RenderedDocument::embeds`]: struct.RenderedDocument.html#method.embeds
+    pub fn set_embed_id(&mut self, embed_id: Option<String>) {
+        self.embed_id = embed_id;
+    }
+
+    /// Marks this element as the toggle of the collapsible section with the given index.
+    ///
+    /// The element can be focused like a link, and selecting it opens or closes the section, see
+    /// [`RenderedDocument::push_section`][].  If the text of the element is one of the markers
+    /// `▾ ` (open) and `▸ ` (closed), [`MarkupView`][] replaces it with the marker that matches
+    /// the state of the section.  Other texts are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_core::event::{Event, Key};
+    /// use cursive_core::View as _;
+    /// use cursive_markup::{Element, MarkupView, RenderedDocument, Renderer};
+    ///
+    /// struct Details;
+    ///
+    /// impl Renderer for Details {
+    ///     fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
+    ///         let mut toggle = Element::plain("[details]".to_owned());
+    ///         toggle.set_section_toggle(Some(0));
+    ///         let mut doc = RenderedDocument::new(constraint);
+    ///         doc.push_line(vec![toggle]);
+    ///         doc.push_line(vec![Element::plain("body".to_owned())]);
+    ///         doc.push_section(1..2, false);
+    ///         doc
+    ///     }
+    /// }
+    ///
+    /// let mut view = MarkupView::with_renderer(Details);
+    /// view.layout((80, 10).into());
+    /// assert_eq!(view.document().unwrap().to_plain_text(), "[details]\n");
+    /// assert!(view.on_event(Event::Key(Key::Enter)).is_consumed());
+    /// view.layout((80, 10).into());
+    /// assert_eq!(view.document().unwrap().to_plain_text(), "[details]\nbody\n");
+    /// ```
+    ///
+    /// [`RenderedDocument::push_section`]: struct.RenderedDocument.html#method.push_section
+    /// [`MarkupView`]: struct.MarkupView.html
+    pub fn set_section_toggle(&mut self, section: Option<usize>) {
+        self.section_toggle = section;
+    }
 }
 
 impl From<String> for Element {
@@ -401,23 +4071,212 @@ impl From<String> for Element {
     }
 }
 
+impl From<&str> for Element {
+    fn from(s: &str) -> Element {
+        Element::plain(s.to_owned())
+    }
+}
+
+impl From<(&str, theme::Style)> for Element {
+    fn from((s, style): (&str, theme::Style)) -> Element {
+        Element::styled(s.to_owned(), style)
+    }
+}
+
 impl From<Element> for RenderedElement {
     fn from(element: Element) -> RenderedElement {
         RenderedElement {
+            width: element.text.width(),
             text: element.text,
             style: element.style,
             link_idx: None,
+            embed_id: element.embed_id,
+        }
+    }
+}
+
+impl LineWrapper {
+    fn new(width: usize) -> LineWrapper {
+        let width = std::cmp::max(width, 1);
+        LineWrapper {
+            width,
+            first_width: width,
+            lines: Vec::new(),
+            line: Vec::new(),
+            line_width: 0,
+            space: Vec::new(),
+            space_width: 0,
+            word: Vec::new(),
+            word_width: 0,
+        }
+    }
+
+    /// Sets a different width for the first line, for example to wrap text with a hanging
+    /// indent.
+    #[cfg_attr(not(feature = "html"), allow(dead_code))]
+    fn with_first_width(mut self, width: usize) -> LineWrapper {
+        self.first_width = std::cmp::max(width, 1);
+        self
+    }
+
+    /// Adds the words and the whitespace of the given element.  The text must not contain
+    /// newlines.
+    fn push(&mut self, idx: usize, element: Element) {
+        let mut rest = element.text.as_str();
+        // Non-breaking spaces are part of the words.
+        let is_break =
+            |c: char| c.is_whitespace() && !matches!(c, '\u{a0}' | '\u{2007}' | '\u{202f}');
+        while let Some(c) = rest.chars().next() {
+            let is_space = is_break(c);
+            let end = rest
+                .find(|c: char| is_break(c) != is_space)
+                .unwrap_or(rest.len());
+            let part = Element {
+                text: rest[..end].to_owned(),
+                ..element.clone()
+            };
+            if is_space {
+                self.push_space(idx, part);
+            } else {
+                self.push_word(idx, part);
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    /// Adds the last word and returns the wrapped lines.
+    fn finish(mut self) -> Vec<Vec<Element>> {
+        self.flush_word();
+        if !self.line.is_empty() {
+            self.finish_line();
+        }
+        self.lines
+    }
+
+    /// Returns the maximum width of the current line.
+    fn line_limit(&self) -> usize {
+        if self.lines.is_empty() {
+            self.first_width
+        } else {
+            self.width
+        }
+    }
+
+    fn push_space(&mut self, idx: usize, element: Element) {
+        if !self.word.is_empty() {
+            self.flush_word();
+        }
+        self.space_width += element.text.width();
+        self.space.push((idx, element));
+    }
+
+    fn push_word(&mut self, idx: usize, element: Element) {
+        self.word_width += element.text.width();
+        self.word.push((idx, element));
+    }
+
+    /// Adds the current word and the whitespace before it to the current line, starting a new
+    /// line if necessary.
+    fn flush_word(&mut self) {
+        let space = std::mem::take(&mut self.space);
+        let space_width = std::mem::take(&mut self.space_width);
+        let word = std::mem::take(&mut self.word);
+        let word_width = std::mem::take(&mut self.word_width);
+        if word.is_empty() {
+            // Trailing whitespace is removed.
+            return;
+        }
+
+        if self.line_width + space_width + word_width <= self.line_limit() {
+            for (idx, element) in space {
+                self.append(idx, element);
+            }
+        } else if !self.line.is_empty() {
+            self.finish_line();
+        }
+
+        if self.line_width + word_width <= self.line_limit() {
+            for (idx, element) in word {
+                self.append(idx, element);
+            }
+        } else {
+            // The word does not fit into a line, so we have to break it.
+            for (idx, element) in word {
+                for c in element.text.chars() {
+                    let width = c.width().unwrap_or_default();
+                    if self.line_width > 0 && self.line_width + width > self.line_limit() {
+                        self.finish_line();
+                    }
+                    let part = Element {
+                        text: c.to_string(),
+                        ..element.clone()
+                    };
+                    self.append(idx, part);
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, idx: usize, element: Element) {
+        self.line_width += element.text.width();
+        match self.line.last_mut() {
+            Some((last_idx, last)) if *last_idx == idx => last.text.push_str(&element.text),
+            _ => self.line.push((idx, element)),
         }
     }
+
+    fn finish_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        self.lines
+            .push(line.into_iter().map(|(_, element)| element).collect());
+        self.line_width = 0;
+    }
+}
+
+/// Returns whether the given link is the continuation of the previous link, i. e. whether it has
+/// the same target and directly follows it in the same row or starts in the next row.
+fn is_link_part(prev: &Link, link: &Link) -> bool {
+    link.target == prev.target
+        && link.section.is_none()
+        && ((link.position.y == prev.position.y && link.position.x == prev.position.x + prev.width)
+            || link.position.y == prev.position.y + 1)
 }
 
 impl LinkHandler {
     pub fn push(&mut self, link: Link) -> usize {
+        if !self.is_next_part(&link) {
+            self.link_count += 1;
+        }
         self.links.push(link);
+        self.order.push(self.links.len() - 1);
         self.links.len() - 1
     }
 
+    pub fn set_order(&mut self, order: NavigationOrder) {
+        self.order = match order {
+            NavigationOrder::DocumentOrder => (0..self.links.len()).collect(),
+            NavigationOrder::VisualOrder => self.visual_order(),
+        };
+    }
+
+    /// Returns the indices of the links sorted by their position.
+    fn visual_order(&self) -> Vec<usize> {
+        let mut order: Vec<_> = (0..self.links.len()).collect();
+        order.sort_by_key(|&idx| (self.links[idx].position.y, self.links[idx].position.x));
+        order
+    }
+
     pub fn take_focus(&mut self, direction: cursive_core::direction::Direction) -> bool {
+        self.take_focus_in(direction, 0..usize::MAX)
+    }
+
+    /// Focuses the first or last link in the given lines, depending on the direction, or the
+    /// first or last link of the document if there are no links in these lines.
+    pub fn take_focus_in(
+        &mut self,
+        direction: cursive_core::direction::Direction,
+        lines: ops::Range<usize>,
+    ) -> bool {
         if self.links.is_empty() {
             false
         } else {
@@ -429,84 +4288,187 @@ impl LinkHandler {
                 },
                 Direction::Rel(rel) => rel,
             };
+            let links = &self.links;
+            let mut visible = self
+                .order
+                .iter()
+                .copied()
+                .filter(|&idx| lines.contains(&links[idx].position.y));
             self.focus = match rel {
-                Relative::Front => 0,
-                Relative::Back => self.links.len() - 1,
+                Relative::Front => visible.next().unwrap_or(self.order[0]),
+                Relative::Back => visible
+                    .next_back()
+                    .unwrap_or(self.order[self.order.len() - 1]),
             };
             true
         }
     }
 
     pub fn move_focus(&mut self, direction: cursive_core::direction::Absolute) -> bool {
+        let focus = self.find_focus(direction);
+        self.set_focus(focus)
+    }
+
+    /// Returns the index of the link that would be focused when moving the focus in the given
+    /// direction, or `None` if the focus cannot be moved in that direction.
+    pub fn find_focus(&self, direction: cursive_core::direction::Absolute) -> Option<usize> {
         use cursive_core::direction::{Absolute, Relative};
 
         match direction {
-            Absolute::Left => self.move_focus_horizontal(Relative::Front),
-            Absolute::Right => self.move_focus_horizontal(Relative::Back),
-            Absolute::Up => self.move_focus_vertical(Relative::Front),
-            Absolute::Down => self.move_focus_vertical(Relative::Back),
-            Absolute::None => false,
+            Absolute::Left => self.find_focus_horizontal(Relative::Front),
+            Absolute::Right => self.find_focus_horizontal(Relative::Back),
+            Absolute::Up => self.find_focus_vertical(Relative::Front),
+            Absolute::Down => self.find_focus_vertical(Relative::Back),
+            Absolute::None => None,
         }
     }
 
-    fn move_focus_horizontal(&mut self, direction: cursive_core::direction::Relative) -> bool {
+    pub fn move_focus_line_edge(&mut self, direction: cursive_core::direction::Relative) -> bool {
         use cursive_core::direction::Relative;
 
         if self.links.is_empty() {
             return false;
         }
 
+        let y = self.links[self.focus].position.y;
+        let mut iter = self
+            .order
+            .iter()
+            .copied()
+            .filter(|&idx| self.links[idx].position.y == y);
         let new_focus = match direction {
-            Relative::Front => self.focus.checked_sub(1),
-            Relative::Back => {
-                if self.focus < self.links.len() - 1 {
-                    Some(self.focus + 1)
-                } else {
-                    None
-                }
-            }
+            Relative::Front => iter.next(),
+            Relative::Back => iter.next_back(),
         };
 
-        if let Some(new_focus) = new_focus {
-            if self.links[self.focus].position.y == self.links[new_focus].position.y {
+        match new_focus {
+            Some(new_focus) if new_focus != self.focus => {
                 self.focus = new_focus;
                 true
-            } else {
-                false
             }
+            _ => false,
+        }
+    }
+
+    /// Focuses the next link in navigation order, optionally wrapping around at the last link.
+    pub fn focus_next(&mut self, wrap: bool) -> bool {
+        let pos = self.focus_position();
+        let next = self.order[pos + 1..]
+            .iter()
+            .chain(self.order[..pos].iter().filter(|_| wrap))
+            .copied()
+            .find(|&idx| self.is_distinct(idx));
+        self.set_focus(next)
+    }
+
+    /// Focuses the previous link in navigation order, optionally wrapping around at the first
+    /// link.
+    pub fn focus_prev(&mut self, wrap: bool) -> bool {
+        let pos = self.focus_position();
+        let prev = self.order[..pos]
+            .iter()
+            .rev()
+            .chain(self.order[pos + 1..].iter().rev().filter(|_| wrap))
+            .copied()
+            .find(|&idx| self.is_distinct(idx));
+        self.set_focus(prev)
+    }
+
+    /// Returns the position of the focused link in the navigation order.
+    fn focus_position(&self) -> usize {
+        self.order
+            .iter()
+            .position(|&idx| idx == self.focus)
+            .unwrap_or_default()
+    }
+
+    /// Returns whether the given link can be focused when moving away from the focused link.
+    ///
+    /// If duplicate links are skipped, this is only the case for links with a different target.
+    fn is_distinct(&self, idx: usize) -> bool {
+        !self.skip_duplicates || self.links[idx].target != self.links[self.focus].target
+    }
+
+    fn set_focus(&mut self, focus: Option<usize>) -> bool {
+        if let Some(focus) = focus {
+            self.focus = focus;
+            true
         } else {
             false
         }
     }
 
-    fn move_focus_vertical(&mut self, direction: cursive_core::direction::Relative) -> bool {
+    fn find_focus_horizontal(&self, direction: cursive_core::direction::Relative) -> Option<usize> {
         use cursive_core::direction::Relative;
 
         if self.links.is_empty() {
-            return false;
+            return None;
+        }
+
+        let y = self.links[self.focus].position.y;
+        let pos = self.focus_position();
+        let same_line = |&&idx: &&usize| self.links[idx].position.y == y;
+        match direction {
+            Relative::Front => self.order[..pos]
+                .iter()
+                .rev()
+                .take_while(same_line)
+                .copied()
+                .find(|&idx| self.is_distinct(idx)),
+            Relative::Back => self.order[pos + 1..]
+                .iter()
+                .take_while(same_line)
+                .copied()
+                .find(|&idx| self.is_distinct(idx)),
+        }
+    }
+
+    fn find_focus_vertical(&self, direction: cursive_core::direction::Relative) -> Option<usize> {
+        use cursive_core::direction::Relative;
+
+        if self.links.is_empty() {
+            return None;
         }
 
         // TODO: Currently, we select the first link on a different line.  We could instead select
         // the closest link on a different line (if there are multiple links on one line).
 
         let y = self.links[self.focus].position.y;
-        let iter = self.links.iter().enumerate();
+        let pos = self.focus_position();
+        let links = &self.links;
         let next = match direction {
-            Relative::Front => iter
+            Relative::Front => self.order[..pos]
+                .iter()
                 .rev()
-                .skip(self.links.len() - self.focus)
-                .find(|(_, link)| link.position.y < y),
-            Relative::Back => iter
-                .skip(self.focus + 1)
-                .find(|(_, link)| link.position.y > y),
+                .find(|&&idx| links[idx].position.y < y && self.is_distinct(idx)),
+            Relative::Back => self.order[pos + 1..]
+                .iter()
+                .find(|&&idx| links[idx].position.y > y && self.is_distinct(idx)),
         };
+        next.copied()
+    }
 
-        if let Some((idx, _)) = next {
-            self.focus = idx;
-            true
-        } else {
-            false
+    /// Returns whether the given link would continue the last link if it was added.
+    fn is_next_part(&self, link: &Link) -> bool {
+        self.links
+            .last()
+            .map(|prev| is_link_part(prev, link))
+            .unwrap_or_default()
+    }
+
+    /// Returns the indices of the first and the last part of the focused link.
+    fn focused_link_parts(&self) -> Option<(usize, usize)> {
+        self.links.get(self.focus)?;
+        let is_continuation = |idx: usize| is_link_part(&self.links[idx - 1], &self.links[idx]);
+        let mut start = self.focus;
+        while start > 0 && is_continuation(start) {
+            start -= 1;
+        }
+        let mut end = self.focus;
+        while end + 1 < self.links.len() && is_continuation(end + 1) {
+            end += 1;
         }
+        Some((start, end))
     }
 
     pub fn important_area(&self) -> cursive_core::Rect {
@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2021 Robin Krahl <robin.krahl@ireas.org>
+// SPDX-License-Identifier: Apache-2.0 or MIT
+
+//! A renderer for plain text.
+//!
+//! This module provides the [`Renderer`][] struct, a renderer that displays text as it is, for
+//! example a `text/plain` document.  In contrast to the [`ansi::Renderer`][], escape sequences are
+//! not interpreted.  Control characters are removed per default, see
+//! [`Renderer::set_control_char_display`][].
+//!
+//! [`Renderer`]: struct.Renderer.html
+//! [`Renderer::set_control_char_display`]: struct.Renderer.html#method.set_control_char_display
+//! [`ansi::Renderer`]: ../ansi/struct.Renderer.html
+
+use unicode_width::UnicodeWidthChar as _;
+
+use crate::{ControlCharDisplay, Element, RenderedDocument};
+
+/// A renderer for plain text.
+///
+/// Lines that are longer than the available width are wrapped at whitespace, and words that are
+/// wider than the available width are broken at the last character that fits into the line.
+/// Tabs are expanded to the next multiple of eight columns.  The rendered lines are mapped to the
+/// lines of the text, see [`RenderedDocument::source_line`][].
+///
+/// # Example
+///
+/// ```
+/// let renderer = cursive_markup::plain::Renderer::new("\x1b[1mnot bold\x1b[0m\n\nsecond line");
+/// let doc = cursive_markup::render_to_document(&renderer, 8);
+/// assert_eq!(doc.to_plain_text(), "[1mnot\nbold[0m\n\nsecond\nline\n");
+/// assert_eq!(doc.source_line(4), Some(2));
+/// ```
+///
+/// [`RenderedDocument::source_line`]: ../struct.RenderedDocument.html#method.source_line
+#[derive(Clone, Debug)]
+pub struct Renderer {
+    lines: Vec<String>,
+    control_char_display: ControlCharDisplay,
+}
+
+impl Renderer {
+    /// Creates a new renderer for the given text.
+    pub fn new(text: &str) -> Renderer {
+        let lines = text
+            .lines()
+            .map(|line| {
+                let mut expanded = String::with_capacity(line.len());
+                let mut column = 0;
+                for c in line.chars() {
+                    if c == '\t' {
+                        let n = 8 - column % 8;
+                        expanded.push_str(&" ".repeat(n));
+                        column += n;
+                    } else {
+                        expanded.push(c);
+                        column += c.width().unwrap_or_default();
+                    }
+                }
+                expanded
+            })
+            .collect();
+        Renderer {
+            lines,
+            control_char_display: ControlCharDisplay::Strip,
+        }
+    }
+
+    /// Sets the display of control characters.
+    ///
+    /// Line breaks and tabs are always handled by the renderer.  As the control characters are
+    /// replaced before wrapping, the lines are wrapped according to the width of the
+    /// replacement.  Per default, control characters are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cursive_markup::ControlCharDisplay;
+    ///
+    /// let mut renderer = cursive_markup::plain::Renderer::new("press \x1b to quit");
+    /// renderer.set_control_char_display(ControlCharDisplay::Caret);
+    /// let doc = cursive_markup::render_to_document(&renderer, 80);
+    /// assert_eq!(doc.to_plain_text(), "press ^[ to quit\n");
+    /// ```
+    pub fn set_control_char_display(&mut self, display: ControlCharDisplay) {
+        self.control_char_display = display;
+    }
+}
+
+impl super::Renderer for Renderer {
+    fn render(&self, constraint: cursive_core::XY<usize>) -> RenderedDocument {
+        let mut doc = RenderedDocument::new(constraint);
+        let width = std::cmp::max(1, constraint.x);
+        for (source_line, line) in self.lines.iter().enumerate() {
+            let start = doc.lines.len();
+            let text = crate::replace_control_chars(line, self.control_char_display);
+            if text.trim().is_empty() {
+                doc.push_line(Vec::new());
+            } else {
+                doc.push_wrapped(vec![Element::plain(text.into_owned())], width);
+            }
+            for line in start..doc.lines.len() {
+                doc.set_source_line(line, source_line);
+            }
+        }
+        doc
+    }
+}